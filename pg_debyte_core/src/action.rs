@@ -1,5 +1,10 @@
+use crate::dictionary::Dictionary;
+use crate::envelope::ActionHeaders;
 use crate::error::DecodeError;
-use crate::types::{DecodeLimits, EncodeLimits};
+use crate::keys::KeyBytes;
+use crate::registry::Registry;
+use crate::types::{DecodeLimits, EncodeLimits, TypeKey};
+use std::borrow::Cow;
 use std::io::Read;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -42,8 +47,188 @@ pub trait ByteAction: Send + Sync {
         limits: &EncodeLimits,
         params: &[u8],
     ) -> Result<Vec<u8>, DecodeError>;
+    /// Params recorded in the envelope for `input`; `encode` and `decode` both receive them.
+    fn encode_params(&self, _input: &[u8], params: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        Ok(params.to_vec())
+    }
+    /// `encode_params` with the envelope it belongs to; keyed actions override this.
+    fn encode_params_in(
+        &self,
+        _context: &ActionContext<'_>,
+        input: &[u8],
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        self.encode_params(input, params)
+    }
+    /// Params with the action's data key re-wrapped under `new_key_id`, leaving the
+    /// payload as is; `None` for actions without a wrapped key.
+    fn rotate_key(
+        &self,
+        _context: &ActionContext<'_>,
+        _params: &[u8],
+        _new_key_id: u32,
+    ) -> Result<Option<Vec<u8>>, DecodeError> {
+        Ok(None)
+    }
+    /// `decode` with the envelope it belongs to; keyed actions override this.
+    fn decode_in(
+        &self,
+        _context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &DecodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        self.decode(input, limits, params)
+    }
+    /// `encode` with the envelope it belongs to; keyed actions override this.
+    fn encode_in(
+        &self,
+        _context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        self.encode(input, limits, params)
+    }
+}
+
+/// Registry, type key and codec id of the envelope an action runs for.
+#[derive(Clone, Copy)]
+pub struct ActionContext<'a> {
+    pub registry: &'a dyn Registry,
+    pub key: Option<TypeKey>,
+    pub codec_id: Option<u16>,
 }
 
+impl<'a> ActionContext<'a> {
+    pub const fn new(registry: &'a dyn Registry) -> Self {
+        Self {
+            registry,
+            key: None,
+            codec_id: None,
+        }
+    }
+
+    pub const fn with_key(mut self, key: TypeKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    pub const fn with_codec_id(mut self, codec_id: u16) -> Self {
+        self.codec_id = Some(codec_id);
+        self
+    }
+
+    /// The type key as laid out in the envelope header, for use as associated data.
+    pub fn associated_data(&self) -> Option<[u8; 18]> {
+        let key = self.key?;
+        let mut data = [0u8; 18];
+        data[..16].copy_from_slice(key.type_id.as_bytes());
+        data[16..].copy_from_slice(&key.schema_version.to_le_bytes());
+        Some(data)
+    }
+
+    /// Looks up `key_id` with the registry's key provider.
+    pub fn key_material(&self, key_id: u32) -> Result<KeyBytes, DecodeError> {
+        let provider = self
+            .registry
+            .key_provider()
+            .ok_or(DecodeError::UnknownKey(key_id))?;
+        provider.key(key_id)?.ok_or(DecodeError::UnknownKey(key_id))
+    }
+
+    /// Looks up dictionary `id` with the registry's dictionary provider.
+    pub fn dictionary(&self, id: u32) -> Result<Dictionary, DecodeError> {
+        let provider = self
+            .registry
+            .dictionary_provider()
+            .ok_or(DecodeError::UnknownDictionary(id))?;
+        provider
+            .dictionary(id)?
+            .ok_or(DecodeError::UnknownDictionary(id))
+    }
+}
+
+/// Fails with `MissingAction` unless every id in `required` is among `present`
+/// (see [`DecoderEntry::required_actions`](crate::DecoderEntry::required_actions)).
+pub fn ensure_required_actions(
+    required: &[u16],
+    present: impl IntoIterator<Item = u16>,
+) -> Result<(), DecodeError> {
+    let present: Vec<u16> = present.into_iter().collect();
+    match required.iter().find(|id| !present.contains(id)) {
+        Some(id) => Err(DecodeError::MissingAction(*id)),
+        None => Ok(()),
+    }
+}
+
+/// Undoes envelope actions in reverse order; borrows the payload when there are none.
+///
+/// Runs without a type key; use [`decode_actions_in`] for envelopes with keyed actions.
+pub fn decode_actions<'a>(
+    registry: &dyn Registry,
+    actions: ActionHeaders<'_>,
+    payload: &'a [u8],
+    limits: &DecodeLimits,
+) -> Result<Cow<'a, [u8]>, DecodeError> {
+    decode_actions_in(&ActionContext::new(registry), actions, payload, limits)
+}
+
+/// Like [`decode_actions`], passing `context` to every action.
+pub fn decode_actions_in<'a>(
+    context: &ActionContext<'_>,
+    actions: ActionHeaders<'_>,
+    payload: &'a [u8],
+    limits: &DecodeLimits,
+) -> Result<Cow<'a, [u8]>, DecodeError> {
+    let headers: Vec<_> = actions.iter().collect();
+    if headers.len() != actions.len() {
+        return Err(DecodeError::BadEnvelope("action header out of bounds"));
+    }
+    let mut buffer = Cow::Borrowed(payload);
+    for action in headers.into_iter().rev() {
+        let handler = context
+            .registry
+            .lookup_action(action.id)
+            .ok_or(DecodeError::UnknownAction(action.id))?;
+        buffer = Cow::Owned(handler.decode_in(context, &buffer, limits, action.params)?);
+    }
+    Ok(buffer)
+}
+
+pub fn encode_actions(
+    registry: &dyn Registry,
+    actions: &[ActionSpec],
+    payload: Vec<u8>,
+    limits: &EncodeLimits,
+) -> Result<Vec<u8>, DecodeError> {
+    encode_actions_in(&ActionContext::new(registry), actions, payload, limits)
+        .map(|(payload, _)| payload)
+}
+
+/// Like [`encode_actions`], passing `context` to every action and also returning the specs
+/// with the params to store in the header (see [`ByteAction::encode_params`]).
+pub fn encode_actions_in(
+    context: &ActionContext<'_>,
+    actions: &[ActionSpec],
+    mut payload: Vec<u8>,
+    limits: &EncodeLimits,
+) -> Result<(Vec<u8>, Vec<ActionSpec>), DecodeError> {
+    let mut recorded = Vec::with_capacity(actions.len());
+    for action in actions {
+        let handler = context
+            .registry
+            .lookup_action(action.id)
+            .ok_or(DecodeError::UnknownAction(action.id))?;
+        let params = handler.encode_params_in(context, &payload, &action.params)?;
+        payload = handler.encode_in(context, &payload, limits, &params)?;
+        recorded.push(ActionSpec::new(action.id, action.flags, params));
+    }
+    Ok((payload, recorded))
+}
+
+/// Params are `[level]`, or `[level, dictionary id (u32 LE)]` to compress with a dictionary
+/// from the registry's dictionary provider. Empty params use level 0 and no dictionary.
 #[derive(Debug, Clone, Copy)]
 pub struct ZstdAction {
     pub id: u16,
@@ -53,6 +238,33 @@ impl ZstdAction {
     pub const fn new(id: u16) -> Self {
         Self { id }
     }
+
+    /// Params selecting `dictionary_id` at `level`.
+    pub fn dictionary_params(level: u8, dictionary_id: u32) -> Vec<u8> {
+        let mut params = vec![level];
+        params.extend_from_slice(&dictionary_id.to_le_bytes());
+        params
+    }
+
+    /// Decoders used to read only `params[0]`, so rows may carry extra bytes after the
+    /// level: only exactly `[level, id (4 bytes)]` selects a dictionary.
+    fn dictionary_id(params: &[u8]) -> Option<u32> {
+        match *params {
+            [_, a, b, c, d] => Some(u32::from_le_bytes([a, b, c, d])),
+            _ => None,
+        }
+    }
+
+    /// Encode writes new params, so it only accepts the two documented layouts.
+    fn encode_dictionary_id(&self, params: &[u8]) -> Result<Option<u32>, DecodeError> {
+        match params.len() {
+            0 | 1 | 5 => Ok(Self::dictionary_id(params)),
+            _ => Err(DecodeError::InvalidActionParams {
+                id: self.id,
+                reason: "expected [level] or [level, dictionary id (4 bytes)]".to_string(),
+            }),
+        }
+    }
 }
 
 impl ByteAction for ZstdAction {
@@ -64,27 +276,14 @@ impl ByteAction for ZstdAction {
         &self,
         input: &[u8],
         limits: &DecodeLimits,
-        _params: &[u8],
+        params: &[u8],
     ) -> Result<Vec<u8>, DecodeError> {
-        let mut decoder = zstd::stream::read::Decoder::new(input)
-            .map_err(|err| DecodeError::Zstd(err.to_string()))?;
-        let mut output = Vec::new();
-        let mut buffer = [0u8; 8192];
-        loop {
-            let read = decoder.read(&mut buffer)?;
-            if read == 0 {
-                break;
-            }
-            if output.len().saturating_add(read) > limits.max_output_bytes {
-                return Err(DecodeError::LimitExceeded {
-                    context: "action_output_bytes",
-                    limit: limits.max_output_bytes,
-                    actual: output.len().saturating_add(read),
-                });
-            }
-            output.extend_from_slice(&buffer[..read]);
+        if let Some(dictionary_id) = Self::dictionary_id(params) {
+            return Err(DecodeError::UnknownDictionary(dictionary_id));
         }
-        Ok(output)
+        let decoder = zstd::stream::read::Decoder::new(input)
+            .map_err(|err| DecodeError::Zstd(err.to_string()))?;
+        read_bounded(decoder, limits, DecodeError::from)
     }
 
     fn encode(
@@ -93,16 +292,493 @@ impl ByteAction for ZstdAction {
         limits: &EncodeLimits,
         params: &[u8],
     ) -> Result<Vec<u8>, DecodeError> {
+        if let Some(dictionary_id) = self.encode_dictionary_id(params)? {
+            return Err(DecodeError::UnknownDictionary(dictionary_id));
+        }
         let level = params.first().map(|b| *b as i32).unwrap_or(0);
         let output =
             zstd::encode_all(input, level).map_err(|err| DecodeError::Zstd(err.to_string()))?;
-        if output.len() > limits.max_output_bytes {
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+
+    fn encode_params(&self, _input: &[u8], params: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        self.encode_dictionary_id(params)?;
+        Ok(params.to_vec())
+    }
+
+    fn decode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &DecodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let Some(dictionary_id) = Self::dictionary_id(params) else {
+            return self.decode(input, limits, params);
+        };
+        let dictionary = context.dictionary(dictionary_id)?;
+        let decoder = zstd::stream::read::Decoder::with_dictionary(input, &dictionary)
+            .map_err(|err| DecodeError::Zstd(err.to_string()))?;
+        read_bounded(decoder, limits, DecodeError::from)
+    }
+
+    fn encode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let Some(dictionary_id) = self.encode_dictionary_id(params)? else {
+            return self.encode(input, limits, params);
+        };
+        let dictionary = context.dictionary(dictionary_id)?;
+        let mut compressor = zstd::bulk::Compressor::with_dictionary(params[0] as i32, &dictionary)
+            .map_err(|err| DecodeError::Zstd(err.to_string()))?;
+        let output = compressor
+            .compress(input)
+            .map_err(|err| DecodeError::Zstd(err.to_string()))?;
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "brotli")]
+#[derive(Debug, Clone, Copy)]
+pub struct BrotliAction {
+    pub id: u16,
+}
+
+#[cfg(feature = "brotli")]
+impl BrotliAction {
+    pub const fn new(id: u16) -> Self {
+        Self { id }
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl ByteAction for BrotliAction {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode(
+        &self,
+        input: &[u8],
+        limits: &DecodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let decoder = brotli::Decompressor::new(input, 4096);
+        read_bounded(decoder, limits, |err| DecodeError::Brotli(err.to_string()))
+    }
+
+    /// `params[0]` is the quality (0-11, default 11) and `params[1]` the
+    /// log2 window size (10-24, default 22).
+    fn encode(
+        &self,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let quality = brotli_param(self.id, params.first(), 0..=11, 11, "quality must be 0-11")?;
+        let window = brotli_param(
+            self.id,
+            params.get(1),
+            10..=24,
+            22,
+            "window size must be 10-24",
+        )?;
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, quality, window);
+        std::io::Write::write_all(&mut encoder, input)
+            .map_err(|err| DecodeError::Brotli(err.to_string()))?;
+        // `into_inner` finishes the stream.
+        let output = encoder.into_inner();
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "brotli")]
+fn brotli_param(
+    id: u16,
+    param: Option<&u8>,
+    range: std::ops::RangeInclusive<u32>,
+    default: u32,
+    reason: &str,
+) -> Result<u32, DecodeError> {
+    match param {
+        None => Ok(default),
+        Some(&value) if range.contains(&value.into()) => Ok(value.into()),
+        Some(_) => Err(DecodeError::InvalidActionParams {
+            id,
+            reason: reason.to_string(),
+        }),
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[derive(Debug, Clone, Copy)]
+pub struct GzipAction {
+    pub id: u16,
+}
+
+#[cfg(feature = "gzip")]
+impl GzipAction {
+    pub const fn new(id: u16) -> Self {
+        Self { id }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl ByteAction for GzipAction {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Reads every gzip member, as `gunzip` and Java's `GZIPInputStream` do.
+    fn decode(
+        &self,
+        input: &[u8],
+        limits: &DecodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let decoder = flate2::read::MultiGzDecoder::new(input);
+        read_bounded(decoder, limits, |err| DecodeError::Deflate(err.to_string()))
+    }
+
+    fn encode(
+        &self,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let level = flate2_level(self.id, params)?;
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+        flate2_finish(encoder, input, limits, flate2::write::GzEncoder::finish)
+    }
+}
+
+/// Raw deflate (RFC 1951) without zlib or gzip framing.
+#[cfg(feature = "gzip")]
+#[derive(Debug, Clone, Copy)]
+pub struct DeflateAction {
+    pub id: u16,
+}
+
+#[cfg(feature = "gzip")]
+impl DeflateAction {
+    pub const fn new(id: u16) -> Self {
+        Self { id }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl ByteAction for DeflateAction {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode(
+        &self,
+        input: &[u8],
+        limits: &DecodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let decoder = flate2::read::DeflateDecoder::new(input);
+        read_bounded(decoder, limits, |err| DecodeError::Deflate(err.to_string()))
+    }
+
+    fn encode(
+        &self,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let level = flate2_level(self.id, params)?;
+        let encoder = flate2::write::DeflateEncoder::new(Vec::new(), level);
+        flate2_finish(
+            encoder,
+            input,
+            limits,
+            flate2::write::DeflateEncoder::finish,
+        )
+    }
+}
+
+/// `params[0]` is the compression level (0-9); defaults to 6.
+#[cfg(feature = "gzip")]
+fn flate2_level(id: u16, params: &[u8]) -> Result<flate2::Compression, DecodeError> {
+    match params.first() {
+        None => Ok(flate2::Compression::default()),
+        Some(&level) if level <= 9 => Ok(flate2::Compression::new(level.into())),
+        Some(_) => Err(DecodeError::InvalidActionParams {
+            id,
+            reason: "compression level must be 0-9".to_string(),
+        }),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn flate2_finish<E: std::io::Write>(
+    mut encoder: E,
+    input: &[u8],
+    limits: &EncodeLimits,
+    finish: fn(E) -> std::io::Result<Vec<u8>>,
+) -> Result<Vec<u8>, DecodeError> {
+    encoder
+        .write_all(input)
+        .map_err(|err| DecodeError::Deflate(err.to_string()))?;
+    let output = finish(encoder).map_err(|err| DecodeError::Deflate(err.to_string()))?;
+    ensure_encoded_len(&output, limits)?;
+    Ok(output)
+}
+
+#[cfg(feature = "lz4")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lz4Format {
+    /// LZ4 frame format (`lz4` CLI, `LZ4FrameOutputStream`).
+    Frame,
+    /// Raw LZ4 block; params carry the uncompressed size as a little-endian u32.
+    Block,
+}
+
+#[cfg(feature = "lz4")]
+#[derive(Debug, Clone, Copy)]
+pub struct Lz4Action {
+    pub id: u16,
+    pub format: Lz4Format,
+}
+
+#[cfg(feature = "lz4")]
+impl Lz4Action {
+    pub const fn new(id: u16) -> Self {
+        Self {
+            id,
+            format: Lz4Format::Frame,
+        }
+    }
+
+    pub const fn with_format(mut self, format: Lz4Format) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl ByteAction for Lz4Action {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode(
+        &self,
+        input: &[u8],
+        limits: &DecodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        match self.format {
+            Lz4Format::Frame => {
+                let decoder = lz4_flex::frame::FrameDecoder::new(input);
+                read_bounded(decoder, limits, |err| DecodeError::Lz4(err.to_string()))
+            }
+            Lz4Format::Block => {
+                let len = lz4_block_len(self.id, params)?;
+                ensure_decoded_len(len, limits)?;
+                let mut output = vec![0u8; len];
+                let written = lz4_flex::block::decompress_into(input, &mut output)
+                    .map_err(|err| DecodeError::Lz4(err.to_string()))?;
+                if written != len {
+                    return Err(DecodeError::Lz4(format!(
+                        "block decoded to {written} bytes, expected {len}"
+                    )));
+                }
+                Ok(output)
+            }
+        }
+    }
+
+    fn encode(
+        &self,
+        input: &[u8],
+        limits: &EncodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let output = match self.format {
+            Lz4Format::Frame => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                std::io::Write::write_all(&mut encoder, input)
+                    .map_err(|err| DecodeError::Lz4(err.to_string()))?;
+                encoder
+                    .finish()
+                    .map_err(|err| DecodeError::Lz4(err.to_string()))?
+            }
+            Lz4Format::Block => lz4_flex::block::compress(input),
+        };
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+
+    fn encode_params(&self, input: &[u8], params: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        match self.format {
+            Lz4Format::Frame => Ok(params.to_vec()),
+            Lz4Format::Block => {
+                let len = u32::try_from(input.len()).map_err(|_| DecodeError::LimitExceeded {
+                    context: "action_input_bytes",
+                    limit: u32::MAX as usize,
+                    actual: input.len(),
+                })?;
+                Ok(len.to_le_bytes().to_vec())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "lz4")]
+fn lz4_block_len(id: u16, params: &[u8]) -> Result<usize, DecodeError> {
+    let len: [u8; 4] = params
+        .try_into()
+        .map_err(|_| DecodeError::InvalidActionParams {
+            id,
+            reason: "lz4 block params must be a 4-byte uncompressed size".to_string(),
+        })?;
+    Ok(u32::from_le_bytes(len) as usize)
+}
+
+#[cfg(feature = "snappy")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnappyFormat {
+    /// Raw snappy block, prefixed with its varint uncompressed length.
+    Raw,
+    /// Snappy framing format with CRC-checked chunks.
+    Frame,
+}
+
+#[cfg(feature = "snappy")]
+#[derive(Debug, Clone, Copy)]
+pub struct SnappyAction {
+    pub id: u16,
+    pub format: SnappyFormat,
+}
+
+#[cfg(feature = "snappy")]
+impl SnappyAction {
+    pub const fn new(id: u16) -> Self {
+        Self {
+            id,
+            format: SnappyFormat::Raw,
+        }
+    }
+
+    pub const fn with_format(mut self, format: SnappyFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[cfg(feature = "snappy")]
+impl ByteAction for SnappyAction {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode(
+        &self,
+        input: &[u8],
+        limits: &DecodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        match self.format {
+            SnappyFormat::Raw => {
+                let len = snap::raw::decompress_len(input)
+                    .map_err(|err| DecodeError::Snappy(err.to_string()))?;
+                ensure_decoded_len(len, limits)?;
+                let mut output = vec![0u8; len];
+                snap::raw::Decoder::new()
+                    .decompress(input, &mut output)
+                    .map_err(|err| DecodeError::Snappy(err.to_string()))?;
+                Ok(output)
+            }
+            SnappyFormat::Frame => {
+                let decoder = snap::read::FrameDecoder::new(input);
+                read_bounded(decoder, limits, |err| DecodeError::Snappy(err.to_string()))
+            }
+        }
+    }
+
+    fn encode(
+        &self,
+        input: &[u8],
+        limits: &EncodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let output = match self.format {
+            SnappyFormat::Raw => snap::raw::Encoder::new()
+                .compress_vec(input)
+                .map_err(|err| DecodeError::Snappy(err.to_string()))?,
+            SnappyFormat::Frame => {
+                let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+                std::io::Write::write_all(&mut encoder, input)
+                    .map_err(|err| DecodeError::Snappy(err.to_string()))?;
+                encoder
+                    .into_inner()
+                    .map_err(|err| DecodeError::Snappy(err.to_string()))?
+            }
+        };
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+}
+
+/// Rejects a declared output size before anything is allocated for it.
+#[cfg(any(feature = "lz4", feature = "snappy"))]
+fn ensure_decoded_len(len: usize, limits: &DecodeLimits) -> Result<(), DecodeError> {
+    if len > limits.max_output_bytes {
+        return Err(DecodeError::LimitExceeded {
+            context: "action_output_bytes",
+            limit: limits.max_output_bytes,
+            actual: len,
+        });
+    }
+    Ok(())
+}
+
+/// Drains `reader` in chunks, failing once the output would pass `max_output_bytes`.
+pub(crate) fn read_bounded<R: Read>(
+    mut reader: R,
+    limits: &DecodeLimits,
+    map_err: impl Fn(std::io::Error) -> DecodeError,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut output = Vec::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(read) => read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(map_err(err)),
+        };
+        if read == 0 {
+            break;
+        }
+        if output.len().saturating_add(read) > limits.max_output_bytes {
             return Err(DecodeError::LimitExceeded {
                 context: "action_output_bytes",
                 limit: limits.max_output_bytes,
-                actual: output.len(),
+                actual: output.len().saturating_add(read),
             });
         }
-        Ok(output)
+        output.extend_from_slice(&buffer[..read]);
+    }
+    Ok(output)
+}
+
+pub(crate) fn ensure_encoded_len(output: &[u8], limits: &EncodeLimits) -> Result<(), DecodeError> {
+    if output.len() > limits.max_output_bytes {
+        return Err(DecodeError::LimitExceeded {
+            context: "action_output_bytes",
+            limit: limits.max_output_bytes,
+            actual: output.len(),
+        });
     }
+    Ok(())
 }
@@ -0,0 +1,379 @@
+use crate::action::{ensure_encoded_len, ActionContext, ByteAction};
+use crate::error::DecodeError;
+use crate::keys::KeyBytes;
+use crate::types::{DecodeLimits, EncodeLimits};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use chacha20poly1305::ChaCha20Poly1305;
+
+/// Length of the little-endian key id at the start of the params.
+pub const KEY_ID_LEN: usize = 4;
+pub const NONCE_LEN: usize = 12;
+pub const TAG_LEN: usize = 16;
+pub const DATA_KEY_LEN: usize = 32;
+/// Data key sealed under a master key: nonce, ciphertext and tag.
+pub const WRAPPED_KEY_LEN: usize = NONCE_LEN + DATA_KEY_LEN + TAG_LEN;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AeadAlgorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+/// Encrypts with a 256-bit key from the registry's key provider.
+///
+/// Callers pass the key id as params; the envelope records it followed by a fresh
+/// random nonce. The envelope's type key is the associated data, so ciphertext
+/// copied under another type fails to decrypt.
+#[derive(Debug, Clone, Copy)]
+pub struct AeadAction {
+    pub id: u16,
+    pub algorithm: AeadAlgorithm,
+}
+
+impl AeadAction {
+    pub const fn new(id: u16, algorithm: AeadAlgorithm) -> Self {
+        Self { id, algorithm }
+    }
+
+    fn split_params<'p>(&self, params: &'p [u8]) -> Result<(u32, &'p [u8]), DecodeError> {
+        if params.len() != KEY_ID_LEN + NONCE_LEN {
+            return Err(invalid_params(self.id, "expected key id and nonce"));
+        }
+        let (key_id, nonce) = params.split_at(KEY_ID_LEN);
+        Ok((read_key_id(key_id), nonce))
+    }
+}
+
+impl ByteAction for AeadAction {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode(
+        &self,
+        _input: &[u8],
+        _limits: &DecodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        Err(no_context(self.id))
+    }
+
+    fn encode(
+        &self,
+        _input: &[u8],
+        _limits: &EncodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        Err(no_context(self.id))
+    }
+
+    /// Appends a random nonce to the caller's key id.
+    fn encode_params(&self, _input: &[u8], params: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        if params.len() != KEY_ID_LEN {
+            return Err(invalid_params(self.id, "expected a 4-byte key id"));
+        }
+        let mut recorded = params.to_vec();
+        recorded.extend_from_slice(&random_nonce()?);
+        Ok(recorded)
+    }
+
+    fn decode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &DecodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let (key_id, nonce) = self.split_params(params)?;
+        ensure_plaintext_len(input, limits)?;
+        let aad = associated_data(self.id, context)?;
+        let key = context.key_material(key_id)?;
+        open(self.algorithm, key_id, &key, nonce, &aad, input)
+    }
+
+    fn encode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let (key_id, nonce) = self.split_params(params)?;
+        let aad = associated_data(self.id, context)?;
+        let key = context.key_material(key_id)?;
+        let output = seal(self.algorithm, key_id, &key, nonce, &aad, input)?;
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+}
+
+/// Envelope encryption: each payload gets a random data key, stored in the params
+/// wrapped by a master key from the registry's key provider.
+///
+/// Callers pass the master key id as params. The envelope records the key id, the
+/// wrapped data key and the payload nonce, so [`rotate_envelope_key`](crate::encode::rotate_envelope_key)
+/// can re-wrap the data key under another master key without touching the payload.
+#[derive(Debug, Clone, Copy)]
+pub struct EnvelopeEncryptionAction {
+    pub id: u16,
+    pub algorithm: AeadAlgorithm,
+}
+
+struct DataKeyParams<'p> {
+    key_id: u32,
+    wrapped_key: &'p [u8],
+    nonce: &'p [u8],
+}
+
+impl EnvelopeEncryptionAction {
+    pub const fn new(id: u16, algorithm: AeadAlgorithm) -> Self {
+        Self { id, algorithm }
+    }
+
+    fn split_params<'p>(&self, params: &'p [u8]) -> Result<DataKeyParams<'p>, DecodeError> {
+        if params.len() != KEY_ID_LEN + WRAPPED_KEY_LEN + NONCE_LEN {
+            return Err(invalid_params(
+                self.id,
+                "expected key id, wrapped data key and nonce",
+            ));
+        }
+        let (key_id, rest) = params.split_at(KEY_ID_LEN);
+        let (wrapped_key, nonce) = rest.split_at(WRAPPED_KEY_LEN);
+        Ok(DataKeyParams {
+            key_id: read_key_id(key_id),
+            wrapped_key,
+            nonce,
+        })
+    }
+
+    fn wrap(
+        &self,
+        context: &ActionContext<'_>,
+        key_id: u32,
+        data_key: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let aad = associated_data(self.id, context)?;
+        let master = context.key_material(key_id)?;
+        let nonce = random_nonce()?;
+        let mut wrapped = nonce.to_vec();
+        wrapped.extend(seal(
+            self.algorithm,
+            key_id,
+            &master,
+            &nonce,
+            &aad,
+            data_key,
+        )?);
+        Ok(wrapped)
+    }
+
+    fn unwrap(
+        &self,
+        context: &ActionContext<'_>,
+        params: &DataKeyParams<'_>,
+    ) -> Result<KeyBytes, DecodeError> {
+        let aad = associated_data(self.id, context)?;
+        let master = context.key_material(params.key_id)?;
+        let (nonce, sealed) = params.wrapped_key.split_at(NONCE_LEN);
+        open(self.algorithm, params.key_id, &master, nonce, &aad, sealed).map(KeyBytes::new)
+    }
+}
+
+impl ByteAction for EnvelopeEncryptionAction {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode(
+        &self,
+        _input: &[u8],
+        _limits: &DecodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        Err(no_context(self.id))
+    }
+
+    fn encode(
+        &self,
+        _input: &[u8],
+        _limits: &EncodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        Err(no_context(self.id))
+    }
+
+    fn encode_params(&self, _input: &[u8], _params: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        Err(no_context(self.id))
+    }
+
+    /// Generates the data key and records it wrapped under the caller's master key id.
+    fn encode_params_in(
+        &self,
+        context: &ActionContext<'_>,
+        _input: &[u8],
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        if params.len() != KEY_ID_LEN {
+            return Err(invalid_params(self.id, "expected a 4-byte master key id"));
+        }
+        let mut data_key = KeyBytes::new(vec![0u8; DATA_KEY_LEN]);
+        fill_random(&mut data_key)?;
+        let mut recorded = params.to_vec();
+        recorded.extend(self.wrap(context, read_key_id(params), &data_key)?);
+        recorded.extend_from_slice(&random_nonce()?);
+        Ok(recorded)
+    }
+
+    fn decode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &DecodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let params = self.split_params(params)?;
+        ensure_plaintext_len(input, limits)?;
+        let data_key = self.unwrap(context, &params)?;
+        let aad = associated_data(self.id, context)?;
+        open(
+            self.algorithm,
+            params.key_id,
+            &data_key,
+            params.nonce,
+            &aad,
+            input,
+        )
+    }
+
+    fn encode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let params = self.split_params(params)?;
+        let data_key = self.unwrap(context, &params)?;
+        let aad = associated_data(self.id, context)?;
+        let output = seal(
+            self.algorithm,
+            params.key_id,
+            &data_key,
+            params.nonce,
+            &aad,
+            input,
+        )?;
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+
+    fn rotate_key(
+        &self,
+        context: &ActionContext<'_>,
+        params: &[u8],
+        new_key_id: u32,
+    ) -> Result<Option<Vec<u8>>, DecodeError> {
+        let params = self.split_params(params)?;
+        let data_key = self.unwrap(context, &params)?;
+        let mut recorded = new_key_id.to_le_bytes().to_vec();
+        recorded.extend(self.wrap(context, new_key_id, &data_key)?);
+        recorded.extend_from_slice(params.nonce);
+        Ok(Some(recorded))
+    }
+}
+
+fn read_key_id(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn invalid_params(id: u16, reason: &str) -> DecodeError {
+    DecodeError::InvalidActionParams {
+        id,
+        reason: reason.to_string(),
+    }
+}
+
+fn no_context(id: u16) -> DecodeError {
+    DecodeError::Aead(format!("action {id} needs an action context"))
+}
+
+fn associated_data(id: u16, context: &ActionContext<'_>) -> Result<[u8; 18], DecodeError> {
+    context.associated_data().ok_or_else(|| {
+        DecodeError::Aead(format!(
+            "action {id} needs the envelope type key as associated data"
+        ))
+    })
+}
+
+fn ensure_plaintext_len(input: &[u8], limits: &DecodeLimits) -> Result<(), DecodeError> {
+    let plain_len = input
+        .len()
+        .checked_sub(TAG_LEN)
+        .ok_or_else(|| DecodeError::Aead("ciphertext shorter than tag".to_string()))?;
+    if plain_len > limits.max_output_bytes {
+        return Err(DecodeError::LimitExceeded {
+            context: "action_output_bytes",
+            limit: limits.max_output_bytes,
+            actual: plain_len,
+        });
+    }
+    Ok(())
+}
+
+fn fill_random(bytes: &mut [u8]) -> Result<(), DecodeError> {
+    OsRng
+        .try_fill_bytes(bytes)
+        .map_err(|err| DecodeError::Aead(format!("random generation failed: {err}")))
+}
+
+fn random_nonce() -> Result<[u8; NONCE_LEN], DecodeError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    fill_random(&mut nonce)?;
+    Ok(nonce)
+}
+
+fn seal(
+    algorithm: AeadAlgorithm,
+    key_id: u32,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    msg: &[u8],
+) -> Result<Vec<u8>, DecodeError> {
+    let nonce = Nonce::from_slice(nonce);
+    let payload = Payload { msg, aad };
+    match algorithm {
+        AeadAlgorithm::Aes256Gcm => cipher::<Aes256Gcm>(key_id, key)?.encrypt(nonce, payload),
+        AeadAlgorithm::ChaCha20Poly1305 => {
+            cipher::<ChaCha20Poly1305>(key_id, key)?.encrypt(nonce, payload)
+        }
+    }
+    .map_err(|_| DecodeError::Aead("encryption failed".to_string()))
+}
+
+fn open(
+    algorithm: AeadAlgorithm,
+    key_id: u32,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    msg: &[u8],
+) -> Result<Vec<u8>, DecodeError> {
+    let nonce = Nonce::from_slice(nonce);
+    let payload = Payload { msg, aad };
+    match algorithm {
+        AeadAlgorithm::Aes256Gcm => cipher::<Aes256Gcm>(key_id, key)?.decrypt(nonce, payload),
+        AeadAlgorithm::ChaCha20Poly1305 => {
+            cipher::<ChaCha20Poly1305>(key_id, key)?.decrypt(nonce, payload)
+        }
+    }
+    .map_err(|_| DecodeError::Aead(format!("authentication failed for key {key_id}")))
+}
+
+fn cipher<C: KeyInit>(key_id: u32, key: &[u8]) -> Result<C, DecodeError> {
+    C::new_from_slice(key).map_err(|_| {
+        DecodeError::InvalidKey(format!("key {key_id} must be 32 bytes, got {}", key.len()))
+    })
+}
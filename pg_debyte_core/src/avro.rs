@@ -0,0 +1,831 @@
+use crate::action::ActionSpecRef;
+use crate::error::DecodeError;
+use crate::registry::DecoderEntry;
+use crate::types::{DecodeLimits, TypeKey};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const MAX_DEPTH: usize = 128;
+
+/// Parsed Avro schema (JSON form). Named types may be referenced by name,
+/// including recursively.
+#[derive(Debug, Clone)]
+pub struct AvroSchema {
+    root: Schema,
+    names: HashMap<String, Schema>,
+}
+
+#[derive(Debug, Clone)]
+enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long,
+    Float,
+    Double,
+    Bytes,
+    String,
+    Record {
+        name: Name,
+        fields: Vec<Field>,
+    },
+    Enum {
+        name: Name,
+        symbols: Vec<String>,
+        default: Option<String>,
+    },
+    Array(Box<Schema>),
+    Map(Box<Schema>),
+    Union(Vec<Schema>),
+    Fixed {
+        name: Name,
+        size: usize,
+    },
+    Ref(String),
+}
+
+#[derive(Debug, Clone)]
+struct Name {
+    full: String,
+    aliases: Vec<String>,
+}
+
+impl Name {
+    /// Reader-side check: same full name, or the writer's full name is one of our aliases.
+    fn accepts(&self, writer: &Name) -> bool {
+        self.full == writer.full || self.aliases.contains(&writer.full)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    aliases: Vec<String>,
+    schema: Schema,
+    default: Option<Value>,
+}
+
+impl AvroSchema {
+    pub fn parse(json: &str) -> Result<Self, DecodeError> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|err| invalid_schema(err.to_string()))?;
+        let mut names = HashMap::new();
+        let root = parse_schema(&value, None, &mut names)?;
+        Ok(Self { root, names })
+    }
+
+    fn resolve<'s>(&'s self, schema: &'s Schema) -> Result<&'s Schema, DecodeError> {
+        match schema {
+            Schema::Ref(name) => self
+                .names
+                .get(name)
+                .ok_or_else(|| DecodeError::Avro(format!("unknown named type {name}"))),
+            other => Ok(other),
+        }
+    }
+}
+
+/// Decoder entry for Avro binary payloads. Payloads are read with the writer
+/// schema and, when a reader schema is set, projected onto it using Avro's
+/// schema-resolution rules.
+pub struct AvroDecoderEntry {
+    key: TypeKey,
+    codec_id: u16,
+    writer_schema: &'static str,
+    reader_schema: Option<&'static str>,
+    default_actions: &'static [ActionSpecRef],
+    schemas: OnceLock<Result<(AvroSchema, Option<AvroSchema>), String>>,
+}
+
+impl AvroDecoderEntry {
+    /// Uses an embedded writer schema, parsed on first decode.
+    pub const fn new(
+        key: TypeKey,
+        codec_id: u16,
+        writer_schema: &'static str,
+        default_actions: &'static [ActionSpecRef],
+    ) -> Self {
+        Self {
+            key,
+            codec_id,
+            writer_schema,
+            reader_schema: None,
+            default_actions,
+            schemas: OnceLock::new(),
+        }
+    }
+
+    pub const fn with_reader_schema(mut self, reader_schema: &'static str) -> Self {
+        self.reader_schema = Some(reader_schema);
+        self
+    }
+
+    /// Uses schemas parsed at runtime.
+    pub fn from_schemas(
+        key: TypeKey,
+        codec_id: u16,
+        writer: AvroSchema,
+        reader: Option<AvroSchema>,
+        default_actions: &'static [ActionSpecRef],
+    ) -> Self {
+        let entry = Self::new(key, codec_id, "", default_actions);
+        let _ = entry.schemas.set(Ok((writer, reader)));
+        entry
+    }
+
+    fn schemas(&self) -> Result<(&AvroSchema, &AvroSchema), DecodeError> {
+        let (writer, reader) = self
+            .schemas
+            .get_or_init(|| {
+                let message = |err: DecodeError| match err {
+                    DecodeError::Avro(message) => message,
+                    other => other.to_string(),
+                };
+                let writer = AvroSchema::parse(self.writer_schema).map_err(message)?;
+                let reader = self
+                    .reader_schema
+                    .map(AvroSchema::parse)
+                    .transpose()
+                    .map_err(message)?;
+                Ok((writer, reader))
+            })
+            .as_ref()
+            .map_err(|err| DecodeError::Avro(err.clone()))?;
+        Ok((writer, reader.as_ref().unwrap_or(writer)))
+    }
+}
+
+impl DecoderEntry for AvroDecoderEntry {
+    fn key(&self) -> TypeKey {
+        self.key
+    }
+
+    fn codec_id(&self) -> u16 {
+        self.codec_id
+    }
+
+    fn default_actions(&self) -> &'static [ActionSpecRef] {
+        self.default_actions
+    }
+
+    fn decode_payload(
+        &self,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<serde_json::Value, DecodeError> {
+        let (writer, reader) = self.schemas()?;
+        decode_avro(payload, writer, reader, limits)
+    }
+}
+
+/// Decodes one Avro binary datum written with `writer` as seen through `reader`.
+pub fn decode_avro(
+    payload: &[u8],
+    writer: &AvroSchema,
+    reader: &AvroSchema,
+    limits: &DecodeLimits,
+) -> Result<Value, DecodeError> {
+    if payload.len() > limits.max_output_bytes {
+        return Err(DecodeError::LimitExceeded {
+            context: "codec_input_bytes",
+            limit: limits.max_output_bytes,
+            actual: payload.len(),
+        });
+    }
+    let mut decoder = Decoder {
+        input: payload,
+        writer,
+        reader,
+        values: 0,
+        max_values: limits.max_json_bytes,
+    };
+    let value = decoder.read(&writer.root, &reader.root, 0)?;
+    if !decoder.input.is_empty() {
+        return Err(DecodeError::Avro(format!(
+            "{} trailing bytes after datum",
+            decoder.input.len()
+        )));
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    writer: &'a AvroSchema,
+    reader: &'a AvroSchema,
+    values: usize,
+    // Every decoded value renders to at least one JSON byte, so this bounds
+    // zero-width items (nulls, empty records) that would otherwise be free.
+    max_values: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn read(
+        &mut self,
+        writer: &'a Schema,
+        reader: &'a Schema,
+        depth: usize,
+    ) -> Result<Value, DecodeError> {
+        self.enter(depth)?;
+        let writer = self.writer.resolve(writer)?;
+        let reader = self.reader.resolve(reader)?;
+
+        match (writer, reader) {
+            (Schema::Union(branches), _) => {
+                let branch = self.read_union_index(branches)?;
+                self.read(branch, reader, depth + 1)
+            }
+            (_, Schema::Union(branches)) => {
+                let branch = branches
+                    .iter()
+                    .find(|branch| {
+                        self.reader
+                            .resolve(branch)
+                            .is_ok_and(|branch| schemas_match(writer, branch))
+                    })
+                    .ok_or_else(|| mismatch(writer, reader))?;
+                self.read(writer, branch, depth + 1)
+            }
+            (Schema::Null, Schema::Null) => Ok(Value::Null),
+            (Schema::Boolean, Schema::Boolean) => match self.take(1)?[0] {
+                0 => Ok(Value::Bool(false)),
+                1 => Ok(Value::Bool(true)),
+                _ => Err(DecodeError::Avro("invalid boolean".to_string())),
+            },
+            (Schema::Int, Schema::Int | Schema::Long) => Ok(Value::from(self.read_int()?)),
+            (Schema::Long, Schema::Long) => Ok(Value::from(self.read_long()?)),
+            (Schema::Int, Schema::Float | Schema::Double) => {
+                Ok(float_value(f64::from(self.read_int()?)))
+            }
+            (Schema::Long, Schema::Float | Schema::Double) => {
+                Ok(float_value(self.read_long()? as f64))
+            }
+            (Schema::Float, Schema::Float | Schema::Double) => {
+                let bytes = self.take(4)?;
+                let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                Ok(float_value(f64::from(value)))
+            }
+            (Schema::Double, Schema::Double) => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(self.take(8)?);
+                Ok(float_value(f64::from_le_bytes(bytes)))
+            }
+            (Schema::Bytes | Schema::String, Schema::Bytes) => {
+                let bytes = self.read_bytes()?;
+                Ok(Value::String(bytea_text(bytes)))
+            }
+            (Schema::Bytes | Schema::String, Schema::String) => {
+                let bytes = self.read_bytes()?;
+                let text = std::str::from_utf8(bytes)
+                    .map_err(|_| DecodeError::Avro("invalid utf-8 string".to_string()))?;
+                Ok(Value::String(text.to_string()))
+            }
+            (
+                Schema::Record {
+                    name: writer_name,
+                    fields: writer_fields,
+                },
+                Schema::Record {
+                    name: reader_name,
+                    fields: reader_fields,
+                },
+            ) if reader_name.accepts(writer_name) => {
+                self.read_record(writer_fields, reader_fields, depth)
+            }
+            (
+                Schema::Enum {
+                    name: writer_name,
+                    symbols: writer_symbols,
+                    ..
+                },
+                Schema::Enum {
+                    name: reader_name,
+                    symbols: reader_symbols,
+                    default,
+                },
+            ) if reader_name.accepts(writer_name) => {
+                let index = self.read_long()?;
+                let symbol = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| writer_symbols.get(index))
+                    .ok_or_else(|| DecodeError::Avro(format!("enum index {index} out of range")))?;
+                if reader_symbols.contains(symbol) {
+                    Ok(Value::String(symbol.clone()))
+                } else {
+                    default
+                        .clone()
+                        .map(Value::String)
+                        .ok_or_else(|| DecodeError::Avro(format!("unknown enum symbol {symbol}")))
+                }
+            }
+            (Schema::Array(writer_items), Schema::Array(reader_items)) => {
+                let mut items = Vec::new();
+                while let Some(count) = self.read_block_count()? {
+                    for _ in 0..count {
+                        items.push(self.read(writer_items, reader_items, depth + 1)?);
+                    }
+                }
+                Ok(Value::Array(items))
+            }
+            (Schema::Map(writer_values), Schema::Map(reader_values)) => {
+                let mut object = Map::new();
+                while let Some(count) = self.read_block_count()? {
+                    for _ in 0..count {
+                        let key = std::str::from_utf8(self.read_bytes()?)
+                            .map_err(|_| DecodeError::Avro("invalid utf-8 map key".to_string()))?
+                            .to_string();
+                        let value = self.read(writer_values, reader_values, depth + 1)?;
+                        object.insert(key, value);
+                    }
+                }
+                Ok(Value::Object(object))
+            }
+            (
+                Schema::Fixed {
+                    name: writer_name,
+                    size: writer_size,
+                },
+                Schema::Fixed {
+                    name: reader_name,
+                    size: reader_size,
+                },
+            ) if reader_name.accepts(writer_name) && writer_size == reader_size => {
+                Ok(Value::String(bytea_text(self.take(*writer_size)?)))
+            }
+            _ => Err(mismatch(writer, reader)),
+        }
+    }
+
+    fn read_record(
+        &mut self,
+        writer_fields: &'a [Field],
+        reader_fields: &'a [Field],
+        depth: usize,
+    ) -> Result<Value, DecodeError> {
+        let mut values: Vec<Option<Value>> = vec![None; reader_fields.len()];
+        for writer_field in writer_fields {
+            let position = reader_fields.iter().position(|reader_field| {
+                reader_field.name == writer_field.name
+                    || reader_field.aliases.contains(&writer_field.name)
+            });
+            match position {
+                Some(index) => {
+                    values[index] = Some(self.read(
+                        &writer_field.schema,
+                        &reader_fields[index].schema,
+                        depth + 1,
+                    )?);
+                }
+                None => self.skip(&writer_field.schema, depth + 1)?,
+            }
+        }
+
+        let mut object = Map::new();
+        for (field, value) in reader_fields.iter().zip(values) {
+            let value = match (value, &field.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.default_value(&field.schema, default, depth + 1)?,
+                (None, None) => {
+                    return Err(DecodeError::Avro(format!(
+                        "missing field {} without default",
+                        field.name
+                    )))
+                }
+            };
+            object.insert(field.name.clone(), value);
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// Renders a reader-schema default the way a decoded value of `schema` would be:
+    /// Avro writes bytes/fixed defaults as strings of code points 0-255.
+    fn default_value(
+        &mut self,
+        schema: &'a Schema,
+        default: &Value,
+        depth: usize,
+    ) -> Result<Value, DecodeError> {
+        self.enter(depth)?;
+        match (self.reader.resolve(schema)?, default) {
+            (Schema::Bytes | Schema::Fixed { .. }, Value::String(text)) => {
+                let bytes = text
+                    .chars()
+                    .map(|ch| u8::try_from(u32::from(ch)).ok())
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(|| {
+                        DecodeError::Avro("bytes default outside code points 0-255".to_string())
+                    })?;
+                Ok(Value::String(bytea_text(&bytes)))
+            }
+            (Schema::Union(branches), _) => match branches.first() {
+                Some(branch) => self.default_value(branch, default, depth + 1),
+                None => Err(DecodeError::Avro("default for empty union".to_string())),
+            },
+            (Schema::Array(items), Value::Array(values)) => values
+                .iter()
+                .map(|value| self.default_value(items, value, depth + 1))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            (Schema::Map(values), Value::Object(entries)) => {
+                let mut object = Map::new();
+                for (key, value) in entries {
+                    object.insert(key.clone(), self.default_value(values, value, depth + 1)?);
+                }
+                Ok(Value::Object(object))
+            }
+            (Schema::Record { fields, .. }, Value::Object(entries)) => {
+                let mut object = Map::new();
+                for field in fields {
+                    let value = entries
+                        .get(&field.name)
+                        .or(field.default.as_ref())
+                        .ok_or_else(|| {
+                            DecodeError::Avro(format!("default missing field {}", field.name))
+                        })?;
+                    let value = self.default_value(&field.schema, value, depth + 1)?;
+                    object.insert(field.name.clone(), value);
+                }
+                Ok(Value::Object(object))
+            }
+            _ => Ok(default.clone()),
+        }
+    }
+
+    /// Consumes a writer value that has no counterpart in the reader schema.
+    fn skip(&mut self, writer: &'a Schema, depth: usize) -> Result<(), DecodeError> {
+        self.enter(depth)?;
+        match self.writer.resolve(writer)? {
+            Schema::Null => {}
+            Schema::Boolean => {
+                self.take(1)?;
+            }
+            Schema::Int | Schema::Long | Schema::Enum { .. } => {
+                self.read_long()?;
+            }
+            Schema::Float => {
+                self.take(4)?;
+            }
+            Schema::Double => {
+                self.take(8)?;
+            }
+            Schema::Bytes | Schema::String => {
+                self.read_bytes()?;
+            }
+            Schema::Fixed { size, .. } => {
+                self.take(*size)?;
+            }
+            Schema::Record { fields, .. } => {
+                for field in fields {
+                    self.skip(&field.schema, depth + 1)?;
+                }
+            }
+            Schema::Union(branches) => {
+                let branch = self.read_union_index(branches)?;
+                self.skip(branch, depth + 1)?;
+            }
+            Schema::Array(items) => {
+                while let Some(count) = self.read_block_count()? {
+                    for _ in 0..count {
+                        self.skip(items, depth + 1)?;
+                    }
+                }
+            }
+            Schema::Map(values) => {
+                while let Some(count) = self.read_block_count()? {
+                    for _ in 0..count {
+                        self.read_bytes()?;
+                        self.skip(values, depth + 1)?;
+                    }
+                }
+            }
+            Schema::Ref(_) => unreachable!("resolved above"),
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self, depth: usize) -> Result<(), DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::Avro("nesting too deep".to_string()));
+        }
+        self.values += 1;
+        if self.values > self.max_values {
+            return Err(DecodeError::LimitExceeded {
+                context: "codec_output_values",
+                limit: self.max_values,
+                actual: self.values,
+            });
+        }
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.input.len() < len {
+            return Err(DecodeError::Avro("unexpected end of input".to_string()));
+        }
+        let (head, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(head)
+    }
+
+    fn read_long(&mut self) -> Result<i64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+        Err(DecodeError::Avro("varint overflow".to_string()))
+    }
+
+    fn read_int(&mut self) -> Result<i32, DecodeError> {
+        let value = self.read_long()?;
+        i32::try_from(value).map_err(|_| DecodeError::Avro(format!("int out of range: {value}")))
+    }
+
+    fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.read_long()?;
+        usize::try_from(len).map_err(|_| DecodeError::Avro(format!("negative length {len}")))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.read_len()?;
+        self.take(len)
+    }
+
+    fn read_union_index(&mut self, branches: &'a [Schema]) -> Result<&'a Schema, DecodeError> {
+        let index = self.read_long()?;
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| branches.get(index))
+            .ok_or_else(|| DecodeError::Avro(format!("union index {index} out of range")))
+    }
+
+    /// Reads an array/map block header; `None` marks the terminating empty block.
+    fn read_block_count(&mut self) -> Result<Option<u64>, DecodeError> {
+        let count = self.read_long()?;
+        if count == 0 {
+            return Ok(None);
+        }
+        if count < 0 {
+            // Negative counts are followed by the block size in bytes.
+            self.read_len()?;
+        }
+        Ok(Some(count.unsigned_abs()))
+    }
+}
+
+fn schemas_match(writer: &Schema, reader: &Schema) -> bool {
+    match (writer, reader) {
+        (Schema::Null, Schema::Null)
+        | (Schema::Boolean, Schema::Boolean)
+        | (Schema::Int, Schema::Int | Schema::Long | Schema::Float | Schema::Double)
+        | (Schema::Long, Schema::Long | Schema::Float | Schema::Double)
+        | (Schema::Float, Schema::Float | Schema::Double)
+        | (Schema::Double, Schema::Double)
+        | (Schema::Bytes | Schema::String, Schema::Bytes | Schema::String)
+        | (Schema::Array(_), Schema::Array(_))
+        | (Schema::Map(_), Schema::Map(_)) => true,
+        (Schema::Record { name: writer, .. }, Schema::Record { name: reader, .. })
+        | (Schema::Enum { name: writer, .. }, Schema::Enum { name: reader, .. }) => {
+            reader.accepts(writer)
+        }
+        (
+            Schema::Fixed {
+                name: writer,
+                size: writer_size,
+            },
+            Schema::Fixed {
+                name: reader,
+                size: reader_size,
+            },
+        ) => reader.accepts(writer) && writer_size == reader_size,
+        _ => false,
+    }
+}
+
+fn parse_schema(
+    value: &Value,
+    namespace: Option<&str>,
+    names: &mut HashMap<String, Schema>,
+) -> Result<Schema, DecodeError> {
+    match value {
+        Value::String(name) => Ok(parse_type_name(name, namespace, names)),
+        Value::Array(branches) => branches
+            .iter()
+            .map(|branch| parse_schema(branch, namespace, names))
+            .collect::<Result<_, _>>()
+            .map(Schema::Union),
+        Value::Object(object) => {
+            let kind = object
+                .get("type")
+                .ok_or_else(|| invalid_schema("missing \"type\""))?;
+            let kind = match kind {
+                Value::String(kind) => kind.as_str(),
+                nested => return parse_schema(nested, namespace, names),
+            };
+            match kind {
+                "record" | "error" => {
+                    let name = parse_name(object, namespace)?;
+                    let namespace = namespace_of(&name.full);
+                    // Register a forward reference first so fields can recurse.
+                    names.insert(name.full.clone(), Schema::Ref(name.full.clone()));
+                    let fields = object
+                        .get("fields")
+                        .and_then(Value::as_array)
+                        .ok_or_else(|| invalid_schema("record without \"fields\""))?
+                        .iter()
+                        .map(|field| parse_field(field, namespace, names))
+                        .collect::<Result<_, _>>()?;
+                    Ok(register(names, Schema::Record { name, fields }))
+                }
+                "enum" => {
+                    let name = parse_name(object, namespace)?;
+                    let symbols = string_list(object.get("symbols"))
+                        .ok_or_else(|| invalid_schema("enum without \"symbols\""))?;
+                    let default = object
+                        .get("default")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                    Ok(register(
+                        names,
+                        Schema::Enum {
+                            name,
+                            symbols,
+                            default,
+                        },
+                    ))
+                }
+                "fixed" => {
+                    let name = parse_name(object, namespace)?;
+                    let size = object
+                        .get("size")
+                        .and_then(Value::as_u64)
+                        .ok_or_else(|| invalid_schema("fixed without \"size\""))?;
+                    Ok(register(
+                        names,
+                        Schema::Fixed {
+                            name,
+                            size: size as usize,
+                        },
+                    ))
+                }
+                "array" => {
+                    let items = object
+                        .get("items")
+                        .ok_or_else(|| invalid_schema("array without \"items\""))?;
+                    Ok(Schema::Array(Box::new(parse_schema(
+                        items, namespace, names,
+                    )?)))
+                }
+                "map" => {
+                    let values = object
+                        .get("values")
+                        .ok_or_else(|| invalid_schema("map without \"values\""))?;
+                    Ok(Schema::Map(Box::new(parse_schema(
+                        values, namespace, names,
+                    )?)))
+                }
+                // Logical types are read as their underlying primitive.
+                other => Ok(parse_type_name(other, namespace, names)),
+            }
+        }
+        other => Err(invalid_schema(format!("unexpected schema value {other}"))),
+    }
+}
+
+fn parse_type_name(name: &str, namespace: Option<&str>, names: &HashMap<String, Schema>) -> Schema {
+    match name {
+        "null" => Schema::Null,
+        "boolean" => Schema::Boolean,
+        "int" => Schema::Int,
+        "long" => Schema::Long,
+        "float" => Schema::Float,
+        "double" => Schema::Double,
+        "bytes" => Schema::Bytes,
+        "string" => Schema::String,
+        reference => {
+            let qualified = qualify(reference, namespace);
+            if names.contains_key(&qualified) {
+                Schema::Ref(qualified)
+            } else {
+                Schema::Ref(reference.to_string())
+            }
+        }
+    }
+}
+
+fn parse_field(
+    value: &Value,
+    namespace: Option<&str>,
+    names: &mut HashMap<String, Schema>,
+) -> Result<Field, DecodeError> {
+    let name = value
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_schema("field without \"name\""))?;
+    let schema = value
+        .get("type")
+        .ok_or_else(|| invalid_schema(format!("field {name} without \"type\"")))?;
+    Ok(Field {
+        name: name.to_string(),
+        aliases: string_list(value.get("aliases")).unwrap_or_default(),
+        schema: parse_schema(schema, namespace, names)?,
+        default: value.get("default").cloned(),
+    })
+}
+
+fn parse_name(object: &Map<String, Value>, namespace: Option<&str>) -> Result<Name, DecodeError> {
+    let name = object
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_schema("named type without \"name\""))?;
+    let namespace = object
+        .get("namespace")
+        .and_then(Value::as_str)
+        .or(namespace);
+    let full = qualify(name, namespace);
+    let alias_namespace = namespace_of(&full);
+    let aliases = string_list(object.get("aliases"))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|alias| qualify(&alias, alias_namespace))
+        .collect();
+    Ok(Name { full, aliases })
+}
+
+fn register(names: &mut HashMap<String, Schema>, schema: Schema) -> Schema {
+    let full = match &schema {
+        Schema::Record { name, .. } | Schema::Enum { name, .. } | Schema::Fixed { name, .. } => {
+            name.full.clone()
+        }
+        _ => return schema,
+    };
+    names.insert(full, schema.clone());
+    schema
+}
+
+fn qualify(name: &str, namespace: Option<&str>) -> String {
+    match namespace {
+        Some(namespace) if !name.contains('.') && !namespace.is_empty() => {
+            format!("{namespace}.{name}")
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn namespace_of(full: &str) -> Option<&str> {
+    full.rsplit_once('.').map(|(namespace, _)| namespace)
+}
+
+fn string_list(value: Option<&Value>) -> Option<Vec<String>> {
+    value?
+        .as_array()?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string))
+        .collect()
+}
+
+fn float_value(value: f64) -> Value {
+    serde_json::Number::from_f64(value)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn bytea_text(bytes: &[u8]) -> String {
+    format!("\\x{}", hex::encode(bytes))
+}
+
+fn mismatch(writer: &Schema, reader: &Schema) -> DecodeError {
+    DecodeError::Avro(format!(
+        "writer schema {} does not resolve to reader schema {}",
+        kind(writer),
+        kind(reader)
+    ))
+}
+
+fn kind(schema: &Schema) -> String {
+    match schema {
+        Schema::Null => "null".to_string(),
+        Schema::Boolean => "boolean".to_string(),
+        Schema::Int => "int".to_string(),
+        Schema::Long => "long".to_string(),
+        Schema::Float => "float".to_string(),
+        Schema::Double => "double".to_string(),
+        Schema::Bytes => "bytes".to_string(),
+        Schema::String => "string".to_string(),
+        Schema::Record { name, .. } => format!("record {}", name.full),
+        Schema::Enum { name, .. } => format!("enum {}", name.full),
+        Schema::Fixed { name, .. } => format!("fixed {}", name.full),
+        Schema::Array(_) => "array".to_string(),
+        Schema::Map(_) => "map".to_string(),
+        Schema::Union(_) => "union".to_string(),
+        Schema::Ref(name) => name.clone(),
+    }
+}
+
+fn invalid_schema(message: impl std::fmt::Display) -> DecodeError {
+    DecodeError::Avro(format!("invalid schema: {message}"))
+}
@@ -1,8 +1,11 @@
+use crate::dyn_codec::{decode_dyn, DynCodec};
 use crate::error::DecodeError;
 use crate::types::{DecodeLimits, EncodeLimits};
 use bincode::Options;
-use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::de::{DeserializeOwned, DeserializeSeed};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::marker::PhantomData;
 
 pub trait Codec: Send + Sync {
     fn id(&self) -> u16;
@@ -18,15 +21,156 @@ pub trait Codec: Send + Sync {
     ) -> Result<Vec<u8>, DecodeError>;
 }
 
+/// Payload encoding for one value type, used by the `encode_to_*` functions.
+/// Every serde [`Codec`] encodes serde types; `BorshCodec` encodes `borsh` types.
+pub trait PayloadEncoder<T> {
+    fn codec_id(&self) -> u16;
+    fn encode_payload(&self, value: &T, limits: &EncodeLimits) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Payload decoding for one value type, used by
+/// [`TypedDecoderEntry`](crate::TypedDecoderEntry).
+pub trait PayloadDecoder<T> {
+    fn codec_id(&self) -> u16;
+    fn decode_payload(&self, bytes: &[u8], limits: &DecodeLimits) -> Result<T, DecodeError>;
+
+    /// Decodes a payload written with another registered codec.
+    fn decode_payload_with(
+        &self,
+        codec: &dyn DynCodec,
+        _bytes: &[u8],
+        _limits: &DecodeLimits,
+    ) -> Result<T, DecodeError> {
+        Err(DecodeError::UnknownCodec(codec.id()))
+    }
+}
+
+impl<T: Serialize, C: Codec> PayloadEncoder<T> for C {
+    fn codec_id(&self) -> u16 {
+        self.id()
+    }
+
+    fn encode_payload(&self, value: &T, limits: &EncodeLimits) -> Result<Vec<u8>, DecodeError> {
+        self.encode(value, limits)
+    }
+}
+
+impl<T: DeserializeOwned, C: Codec> PayloadDecoder<T> for C {
+    fn codec_id(&self) -> u16 {
+        self.id()
+    }
+
+    fn decode_payload(&self, bytes: &[u8], limits: &DecodeLimits) -> Result<T, DecodeError> {
+        self.decode(bytes, limits)
+    }
+
+    fn decode_payload_with(
+        &self,
+        codec: &dyn DynCodec,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<T, DecodeError> {
+        decode_dyn(codec, bytes, limits)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntEncoding {
+    Varint,
+    Fixint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// Wire options for the bincode codecs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BincodeOptions {
+    pub int_encoding: IntEncoding,
+    pub endian: Endian,
+    pub allow_trailing_bytes: bool,
+}
+
+impl BincodeOptions {
+    /// `bincode::DefaultOptions`: varint, little endian, trailing bytes rejected.
+    pub const DEFAULT: Self = Self {
+        int_encoding: IntEncoding::Varint,
+        endian: Endian::Little,
+        allow_trailing_bytes: false,
+    };
+
+    /// `bincode::serialize` / `bincode::deserialize`: fixint, little endian,
+    /// trailing bytes allowed.
+    pub const LEGACY: Self = Self {
+        int_encoding: IntEncoding::Fixint,
+        endian: Endian::Little,
+        allow_trailing_bytes: true,
+    };
+
+    pub const fn with_int_encoding(mut self, int_encoding: IntEncoding) -> Self {
+        self.int_encoding = int_encoding;
+        self
+    }
+
+    pub const fn with_endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    pub const fn with_trailing_bytes(mut self, allow: bool) -> Self {
+        self.allow_trailing_bytes = allow;
+        self
+    }
+}
+
+impl Default for BincodeOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct BincodeCodec {
     pub id: u16,
     pub byte_limit: u64,
+    pub options: BincodeOptions,
 }
 
 impl BincodeCodec {
     pub const fn new(id: u16, byte_limit: u64) -> Self {
-        Self { id, byte_limit }
+        Self {
+            id,
+            byte_limit,
+            options: BincodeOptions::DEFAULT,
+        }
+    }
+
+    pub const fn with_options(mut self, options: BincodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl BincodeCodec {
+    pub(crate) fn decode_seed<'de, S: DeserializeSeed<'de>>(
+        &self,
+        seed: S,
+        bytes: &'de [u8],
+        limits: &DecodeLimits,
+    ) -> Result<S::Value, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64);
+        if bytes.len() as u64 > limit {
+            return Err(DecodeError::LimitExceeded {
+                context: "codec_input_bytes",
+                limit: limit as usize,
+                actual: bytes.len(),
+            });
+        }
+        with_bincode_options(self.options, limit, BincodeDecode { seed, bytes })
+            .map_err(|err| DecodeError::Bincode(err.to_string()))
     }
 }
 
@@ -40,6 +184,249 @@ impl Codec for BincodeCodec {
         bytes: &[u8],
         limits: &DecodeLimits,
     ) -> Result<T, DecodeError> {
+        self.decode_seed(PhantomData, bytes, limits)
+    }
+
+    fn encode<T: Serialize>(
+        &self,
+        value: &T,
+        limits: &EncodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64);
+        with_bincode_options(self.options, limit, BincodeEncode { value })
+            .map_err(|err| DecodeError::Bincode(err.to_string()))
+    }
+}
+
+/// bincode 1 options are type-level; this turns a runtime `BincodeOptions`
+/// into the matching `Options` type and hands it to the operation.
+trait BincodeOperation {
+    type Output;
+
+    fn run<O: Options>(self, options: O) -> Self::Output;
+}
+
+struct BincodeDecode<'de, S> {
+    seed: S,
+    bytes: &'de [u8],
+}
+
+impl<'de, S: DeserializeSeed<'de>> BincodeOperation for BincodeDecode<'de, S> {
+    type Output = bincode::Result<S::Value>;
+
+    fn run<O: Options>(self, options: O) -> Self::Output {
+        options.deserialize_seed(self.seed, self.bytes)
+    }
+}
+
+struct BincodeEncode<'a, T> {
+    value: &'a T,
+}
+
+impl<T: Serialize> BincodeOperation for BincodeEncode<'_, T> {
+    type Output = bincode::Result<Vec<u8>>;
+
+    fn run<O: Options>(self, options: O) -> Self::Output {
+        options.serialize(self.value)
+    }
+}
+
+fn with_bincode_options<V: BincodeOperation>(
+    options: BincodeOptions,
+    limit: u64,
+    operation: V,
+) -> V::Output {
+    let base = bincode::DefaultOptions::new().with_limit(limit);
+    match options.int_encoding {
+        IntEncoding::Varint => with_endian(base.with_varint_encoding(), options, operation),
+        IntEncoding::Fixint => with_endian(base.with_fixint_encoding(), options, operation),
+    }
+}
+
+fn with_endian<O: Options, V: BincodeOperation>(
+    base: O,
+    options: BincodeOptions,
+    operation: V,
+) -> V::Output {
+    match options.endian {
+        Endian::Little => with_trailing(base.with_little_endian(), options, operation),
+        Endian::Big => with_trailing(base.with_big_endian(), options, operation),
+    }
+}
+
+fn with_trailing<O: Options, V: BincodeOperation>(
+    base: O,
+    options: BincodeOptions,
+    operation: V,
+) -> V::Output {
+    if options.allow_trailing_bytes {
+        operation.run(base.allow_trailing_bytes())
+    } else {
+        operation.run(base.reject_trailing_bytes())
+    }
+}
+
+/// bincode 2.x codec sharing `BincodeOptions` with `BincodeCodec`. bincode 2
+/// only takes limits at compile time, so the byte limit is a const parameter;
+/// runtime `DecodeLimits`/`EncodeLimits` are enforced on top of it.
+#[cfg(feature = "bincode2")]
+#[derive(Debug, Clone, Copy)]
+pub struct Bincode2Codec<const BYTE_LIMIT: usize = { 32 * 1024 * 1024 }> {
+    pub id: u16,
+    pub options: BincodeOptions,
+}
+
+#[cfg(feature = "bincode2")]
+impl<const BYTE_LIMIT: usize> Bincode2Codec<BYTE_LIMIT> {
+    pub const fn new(id: u16) -> Self {
+        Self {
+            id,
+            options: BincodeOptions::DEFAULT,
+        }
+    }
+
+    pub const fn with_options(mut self, options: BincodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn with_config<V: Bincode2Operation>(&self, operation: V) -> V::Output {
+        let base = bincode2::config::standard().with_limit::<BYTE_LIMIT>();
+        match (self.options.int_encoding, self.options.endian) {
+            (IntEncoding::Varint, Endian::Little) => {
+                operation.run(base.with_variable_int_encoding().with_little_endian())
+            }
+            (IntEncoding::Varint, Endian::Big) => {
+                operation.run(base.with_variable_int_encoding().with_big_endian())
+            }
+            (IntEncoding::Fixint, Endian::Little) => {
+                operation.run(base.with_fixed_int_encoding().with_little_endian())
+            }
+            (IntEncoding::Fixint, Endian::Big) => {
+                operation.run(base.with_fixed_int_encoding().with_big_endian())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "bincode2")]
+impl<const BYTE_LIMIT: usize> Bincode2Codec<BYTE_LIMIT> {
+    pub(crate) fn decode_seed<'de, S: DeserializeSeed<'de>>(
+        &self,
+        seed: S,
+        bytes: &'de [u8],
+        limits: &DecodeLimits,
+    ) -> Result<S::Value, DecodeError> {
+        let limit = BYTE_LIMIT.min(limits.max_output_bytes);
+        if bytes.len() > limit {
+            return Err(DecodeError::LimitExceeded {
+                context: "codec_input_bytes",
+                limit,
+                actual: bytes.len(),
+            });
+        }
+        let (value, read) = self
+            .with_config(Bincode2Decode { seed, bytes })
+            .map_err(|err| DecodeError::Bincode(err.to_string()))?;
+        if read != bytes.len() && !self.options.allow_trailing_bytes {
+            return Err(DecodeError::Bincode(format!(
+                "{} trailing bytes after value",
+                bytes.len() - read
+            )));
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(feature = "bincode2")]
+impl<const BYTE_LIMIT: usize> Codec for Bincode2Codec<BYTE_LIMIT> {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<T, DecodeError> {
+        self.decode_seed(PhantomData, bytes, limits)
+    }
+
+    fn encode<T: Serialize>(
+        &self,
+        value: &T,
+        limits: &EncodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let mut writer = LimitedWriter::new(BYTE_LIMIT.min(limits.max_output_bytes));
+        let result = self.with_config(Bincode2Encode {
+            value,
+            writer: &mut writer,
+        });
+        writer.check("codec_output_bytes")?;
+        result.map_err(|err| DecodeError::Bincode(err.to_string()))?;
+        Ok(writer.into_inner())
+    }
+}
+
+#[cfg(feature = "bincode2")]
+trait Bincode2Operation {
+    type Output;
+
+    fn run<C: bincode2::config::Config>(self, config: C) -> Self::Output;
+}
+
+#[cfg(feature = "bincode2")]
+struct Bincode2Decode<'de, S> {
+    seed: S,
+    bytes: &'de [u8],
+}
+
+#[cfg(feature = "bincode2")]
+impl<'de, S: DeserializeSeed<'de>> Bincode2Operation for Bincode2Decode<'de, S> {
+    type Output = Result<(S::Value, usize), bincode2::error::DecodeError>;
+
+    fn run<C: bincode2::config::Config>(self, config: C) -> Self::Output {
+        bincode2::serde::seed_decode_from_slice(self.seed, self.bytes, config)
+    }
+}
+
+#[cfg(feature = "bincode2")]
+struct Bincode2Encode<'a, T> {
+    value: &'a T,
+    writer: &'a mut LimitedWriter,
+}
+
+#[cfg(feature = "bincode2")]
+impl<T: Serialize> Bincode2Operation for Bincode2Encode<'_, T> {
+    type Output = Result<usize, bincode2::error::EncodeError>;
+
+    fn run<C: bincode2::config::Config>(self, config: C) -> Self::Output {
+        bincode2::serde::encode_into_std_write(self.value, self.writer, config)
+    }
+}
+
+#[cfg(feature = "postcard")]
+#[derive(Debug, Clone, Copy)]
+pub struct PostcardCodec {
+    pub id: u16,
+    pub byte_limit: u64,
+}
+
+#[cfg(feature = "postcard")]
+impl PostcardCodec {
+    pub const fn new(id: u16, byte_limit: u64) -> Self {
+        Self { id, byte_limit }
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl PostcardCodec {
+    pub(crate) fn decode_seed<'de, S: DeserializeSeed<'de>>(
+        &self,
+        seed: S,
+        bytes: &'de [u8],
+        limits: &DecodeLimits,
+    ) -> Result<S::Value, DecodeError> {
         let limit = self.byte_limit.min(limits.max_output_bytes as u64);
         if bytes.len() as u64 > limit {
             return Err(DecodeError::LimitExceeded {
@@ -48,10 +435,35 @@ impl Codec for BincodeCodec {
                 actual: bytes.len(),
             });
         }
-        bincode::DefaultOptions::new()
-            .with_limit(limit)
-            .deserialize(bytes)
-            .map_err(|err| DecodeError::Bincode(err.to_string()))
+        let mut deserializer = postcard::Deserializer::from_bytes(bytes);
+        let value = seed
+            .deserialize(&mut deserializer)
+            .map_err(|err| DecodeError::Postcard(err.to_string()))?;
+        let rest = deserializer
+            .finalize()
+            .map_err(|err| DecodeError::Postcard(err.to_string()))?;
+        if !rest.is_empty() {
+            return Err(DecodeError::Postcard(format!(
+                "{} trailing bytes after value",
+                rest.len()
+            )));
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl Codec for PostcardCodec {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<T, DecodeError> {
+        self.decode_seed(PhantomData, bytes, limits)
     }
 
     fn encode<T: Serialize>(
@@ -59,10 +471,357 @@ impl Codec for BincodeCodec {
         value: &T,
         limits: &EncodeLimits,
     ) -> Result<Vec<u8>, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64) as usize;
+        let mut writer = LimitedWriter::new(limit);
+        let result = postcard::to_io(value, &mut writer).map(|_| ());
+        writer.check("codec_output_bytes")?;
+        result.map_err(|err| DecodeError::Postcard(err.to_string()))?;
+        Ok(writer.into_inner())
+    }
+}
+
+/// Borsh codec for types deriving `BorshSerialize`/`BorshDeserialize`, with the same byte
+/// limits as `BincodeCodec`. It is not a serde [`Codec`], so it cannot be registered with
+/// `StaticRegistry::with_codecs`.
+#[cfg(feature = "borsh")]
+#[derive(Debug, Clone, Copy)]
+pub struct BorshCodec {
+    pub id: u16,
+    pub byte_limit: u64,
+}
+
+#[cfg(feature = "borsh")]
+impl BorshCodec {
+    pub const fn new(id: u16, byte_limit: u64) -> Self {
+        Self { id, byte_limit }
+    }
+
+    pub fn decode<T: borsh::BorshDeserialize>(
+        &self,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<T, DecodeError> {
         let limit = self.byte_limit.min(limits.max_output_bytes as u64);
-        bincode::DefaultOptions::new()
-            .with_limit(limit)
-            .serialize(value)
-            .map_err(|err| DecodeError::Bincode(err.to_string()))
+        if bytes.len() as u64 > limit {
+            return Err(DecodeError::LimitExceeded {
+                context: "codec_input_bytes",
+                limit: limit as usize,
+                actual: bytes.len(),
+            });
+        }
+        borsh::from_slice(bytes).map_err(|err| DecodeError::Borsh(err.to_string()))
+    }
+
+    pub fn encode<T: borsh::BorshSerialize>(
+        &self,
+        value: &T,
+        limits: &EncodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64) as usize;
+        let mut writer = LimitedWriter::new(limit);
+        let result = borsh::to_writer(&mut writer, value);
+        writer.check("codec_output_bytes")?;
+        result.map_err(|err| DecodeError::Borsh(err.to_string()))?;
+        Ok(writer.into_inner())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<T: borsh::BorshSerialize> PayloadEncoder<T> for BorshCodec {
+    fn codec_id(&self) -> u16 {
+        self.id
+    }
+
+    fn encode_payload(&self, value: &T, limits: &EncodeLimits) -> Result<Vec<u8>, DecodeError> {
+        self.encode(value, limits)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<T: borsh::BorshDeserialize> PayloadDecoder<T> for BorshCodec {
+    fn codec_id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode_payload(&self, bytes: &[u8], limits: &DecodeLimits) -> Result<T, DecodeError> {
+        self.decode(bytes, limits)
+    }
+}
+
+/// How `RmpCodec` writes structs; decoding accepts either layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RmpStructLayout {
+    /// Structs as maps keyed by field name (`rmp_serde::to_vec_named`).
+    Map,
+    /// Structs as positional arrays (`rmp_serde::to_vec`).
+    Array,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RmpCodec {
+    pub id: u16,
+    pub byte_limit: u64,
+    pub layout: RmpStructLayout,
+}
+
+impl RmpCodec {
+    pub const fn new(id: u16, byte_limit: u64) -> Self {
+        Self {
+            id,
+            byte_limit,
+            layout: RmpStructLayout::Map,
+        }
+    }
+
+    pub const fn with_layout(mut self, layout: RmpStructLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+}
+
+impl RmpCodec {
+    pub(crate) fn decode_seed<'de, S: DeserializeSeed<'de>>(
+        &self,
+        seed: S,
+        bytes: &'de [u8],
+        limits: &DecodeLimits,
+    ) -> Result<S::Value, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64);
+        if bytes.len() as u64 > limit {
+            return Err(DecodeError::LimitExceeded {
+                context: "codec_input_bytes",
+                limit: limit as usize,
+                actual: bytes.len(),
+            });
+        }
+        let mut rest = bytes;
+        let mut deserializer = rmp_serde::Deserializer::new(&mut rest);
+        let value = seed
+            .deserialize(&mut deserializer)
+            .map_err(|err| DecodeError::MessagePack(err.to_string()))?;
+        if !rest.is_empty() {
+            return Err(DecodeError::MessagePack(format!(
+                "{} trailing bytes after value",
+                rest.len()
+            )));
+        }
+        Ok(value)
+    }
+}
+
+impl Codec for RmpCodec {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<T, DecodeError> {
+        self.decode_seed(PhantomData, bytes, limits)
+    }
+
+    fn encode<T: Serialize>(
+        &self,
+        value: &T,
+        limits: &EncodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64) as usize;
+        let mut writer = LimitedWriter::new(limit);
+        let result = match self.layout {
+            RmpStructLayout::Map => rmp_serde::encode::write_named(&mut writer, value),
+            RmpStructLayout::Array => rmp_serde::encode::write(&mut writer, value),
+        };
+        writer.check("codec_output_bytes")?;
+        result.map_err(|err| DecodeError::MessagePack(err.to_string()))?;
+        Ok(writer.into_inner())
+    }
+}
+
+pub const DEFAULT_CBOR_MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CborCodec {
+    pub id: u16,
+    pub byte_limit: u64,
+    pub max_depth: usize,
+}
+
+impl CborCodec {
+    pub const fn new(id: u16, byte_limit: u64) -> Self {
+        Self {
+            id,
+            byte_limit,
+            max_depth: DEFAULT_CBOR_MAX_DEPTH,
+        }
+    }
+
+    pub const fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+impl Codec for CborCodec {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<T, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64);
+        if bytes.len() as u64 > limit {
+            return Err(DecodeError::LimitExceeded {
+                context: "codec_input_bytes",
+                limit: limit as usize,
+                actual: bytes.len(),
+            });
+        }
+        let mut rest = bytes;
+        let value = ciborium::de::from_reader_with_recursion_limit(&mut rest, self.max_depth)
+            .map_err(|err| DecodeError::Cbor(err.to_string()))?;
+        if !rest.is_empty() {
+            return Err(DecodeError::Cbor(format!(
+                "{} trailing bytes after value",
+                rest.len()
+            )));
+        }
+        Ok(value)
+    }
+
+    fn encode<T: Serialize>(
+        &self,
+        value: &T,
+        limits: &EncodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let limit = self.byte_limit.min(limits.max_output_bytes as u64) as usize;
+        let mut writer = LimitedWriter::new(limit);
+        let result = ciborium::ser::into_writer(value, &mut writer);
+        writer.check("codec_output_bytes")?;
+        result.map_err(|err| DecodeError::Cbor(err.to_string()))?;
+        Ok(writer.into_inner())
+    }
+}
+
+/// Untyped CBOR item whose JSON form keeps everything JSON cannot express
+/// natively: byte strings become `"\\x<hex>"`, tags become
+/// `{"tag": n, "value": ...}` and non-string map keys are stringified.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct CborValue(pub ciborium::Value);
+
+impl CborValue {
+    pub fn to_json(&self) -> serde_json::Value {
+        cbor_to_json(&self.0)
+    }
+}
+
+impl Serialize for CborValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+fn cbor_to_json(value: &ciborium::Value) -> serde_json::Value {
+    use ciborium::Value as Cbor;
+    use serde_json::Value as Json;
+
+    match value {
+        Cbor::Integer(int) => integer_to_json(i128::from(*int)),
+        Cbor::Bytes(bytes) => Json::String(bytea_text(bytes)),
+        Cbor::Float(float) => serde_json::Number::from_f64(*float)
+            .map(Json::Number)
+            .unwrap_or(Json::Null),
+        Cbor::Text(text) => Json::String(text.clone()),
+        Cbor::Bool(flag) => Json::Bool(*flag),
+        Cbor::Null => Json::Null,
+        Cbor::Tag(tag, inner) => serde_json::json!({
+            "tag": tag,
+            "value": cbor_to_json(inner),
+        }),
+        Cbor::Array(items) => Json::Array(items.iter().map(cbor_to_json).collect()),
+        Cbor::Map(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(key, value)| (cbor_key(key), cbor_to_json(value)))
+                .collect(),
+        ),
+        _ => Json::Null,
+    }
+}
+
+fn cbor_key(key: &ciborium::Value) -> String {
+    match key {
+        ciborium::Value::Text(text) => text.clone(),
+        ciborium::Value::Integer(int) => i128::from(*int).to_string(),
+        ciborium::Value::Bytes(bytes) => bytea_text(bytes),
+        ciborium::Value::Bool(flag) => flag.to_string(),
+        other => cbor_to_json(other).to_string(),
+    }
+}
+
+fn integer_to_json(int: i128) -> serde_json::Value {
+    if let Ok(int) = i64::try_from(int) {
+        serde_json::Value::from(int)
+    } else if let Ok(int) = u64::try_from(int) {
+        serde_json::Value::from(int)
+    } else {
+        serde_json::Value::String(int.to_string())
+    }
+}
+
+fn bytea_text(bytes: &[u8]) -> String {
+    format!("\\x{}", hex::encode(bytes))
+}
+
+/// `Vec<u8>` writer that refuses to grow past `limit` bytes.
+pub(crate) struct LimitedWriter {
+    buffer: Vec<u8>,
+    limit: usize,
+    attempted: usize,
+}
+
+impl LimitedWriter {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            limit,
+            attempted: 0,
+        }
+    }
+
+    /// Reports the limit breach, if any, after the serializer has returned.
+    pub(crate) fn check(&self, context: &'static str) -> Result<(), DecodeError> {
+        if self.attempted > self.limit {
+            return Err(DecodeError::LimitExceeded {
+                context,
+                limit: self.limit,
+                actual: self.attempted,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+impl Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.attempted = self.buffer.len().saturating_add(buf.len());
+        if self.attempted > self.limit {
+            return Err(std::io::Error::other("output limit exceeded"));
+        }
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
@@ -0,0 +1,132 @@
+use crate::action::ActionSpec;
+use crate::envelope::{validate_actions, ActionHeaders, HeaderLayout};
+use crate::error::DecodeError;
+use crate::registry::Registry;
+use crate::types::TypeKey;
+use crate::varint;
+
+pub const COMPACT_MAGIC: &[u8; 4] = b"PGDC";
+const COMPACT_VERSION: u8 = 1;
+
+/// Compact envelope: a registered type alias instead of the 16-byte type_id and
+/// varint-encoded header fields. Carries no checksum.
+#[derive(Debug)]
+pub struct CompactEnvelopeView<'a> {
+    pub alias: u32,
+    pub schema_version: u16,
+    pub codec_id: u16,
+    pub actions: ActionHeaders<'a>,
+    pub payload: &'a [u8],
+}
+
+impl CompactEnvelopeView<'_> {
+    pub fn resolve_key(&self, registry: &dyn Registry) -> Result<TypeKey, DecodeError> {
+        let type_id = registry
+            .lookup_type_alias(self.alias)
+            .ok_or(DecodeError::UnknownTypeAlias(self.alias))?;
+        Ok(TypeKey {
+            type_id,
+            schema_version: self.schema_version,
+        })
+    }
+}
+
+pub fn try_parse_compact(input: &[u8]) -> Result<Option<CompactEnvelopeView<'_>>, DecodeError> {
+    if input.len() < COMPACT_MAGIC.len() + 1 || &input[..COMPACT_MAGIC.len()] != COMPACT_MAGIC {
+        return Ok(None);
+    }
+
+    let mut offset = COMPACT_MAGIC.len();
+    if input[offset] != COMPACT_VERSION {
+        return Err(DecodeError::BadEnvelope(
+            "unsupported compact envelope version",
+        ));
+    }
+    offset += 1;
+
+    let (alias, len) = varint::read_u32(&input[offset..])?;
+    offset += len;
+    let (schema_version, len) = varint::read_u16(&input[offset..])?;
+    offset += len;
+    let (codec_id, len) = varint::read_u16(&input[offset..])?;
+    offset += len;
+
+    let actions_count = *input
+        .get(offset)
+        .ok_or(DecodeError::BadEnvelope("action count out of bounds"))?;
+    offset += 1;
+    let actions = ActionHeaders::parse(&input[offset..], actions_count, HeaderLayout::Varint)?;
+    offset += actions.encoded_len();
+
+    Ok(Some(CompactEnvelopeView {
+        alias,
+        schema_version,
+        codec_id,
+        actions,
+        payload: &input[offset..],
+    }))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompactEnvelopeBuilder<'a> {
+    alias: u32,
+    schema_version: u16,
+    codec_id: u16,
+    actions: &'a [ActionSpec],
+}
+
+impl<'a> CompactEnvelopeBuilder<'a> {
+    pub fn new(alias: u32, schema_version: u16, codec_id: u16) -> Self {
+        Self {
+            alias,
+            schema_version,
+            codec_id,
+            actions: &[],
+        }
+    }
+
+    /// Looks up the alias registered for `key.type_id`.
+    pub fn for_key(
+        registry: &dyn Registry,
+        key: TypeKey,
+        codec_id: u16,
+    ) -> Result<Self, DecodeError> {
+        let alias = registry
+            .type_alias_for(key.type_id)
+            .ok_or(DecodeError::UnknownType(key))?;
+        Ok(Self::new(alias, key.schema_version, codec_id))
+    }
+
+    pub fn actions(mut self, actions: &'a [ActionSpec]) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), DecodeError> {
+        validate_actions(self.actions)
+    }
+
+    pub fn build(&self, payload: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let mut output = Vec::new();
+        self.build_into(&mut output, payload)?;
+        Ok(output)
+    }
+
+    pub fn build_into(&self, output: &mut Vec<u8>, payload: &[u8]) -> Result<(), DecodeError> {
+        self.validate()?;
+        output.extend_from_slice(COMPACT_MAGIC);
+        output.push(COMPACT_VERSION);
+        varint::write(u64::from(self.alias), output);
+        varint::write(u64::from(self.schema_version), output);
+        varint::write(u64::from(self.codec_id), output);
+        output.push(self.actions.len() as u8);
+        for action in self.actions {
+            varint::write(u64::from(action.id), output);
+            output.push(action.flags);
+            varint::write(action.params.len() as u64, output);
+            output.extend_from_slice(&action.params);
+        }
+        output.extend_from_slice(payload);
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use crate::error::DecodeError;
+use std::sync::Arc;
+
+/// Dictionary bytes shared between the provider's cache and the actions using them.
+pub type Dictionary = Arc<[u8]>;
+
+/// Supplies compression dictionaries by id (see [`Registry::dictionary_provider`](crate::Registry::dictionary_provider)).
+///
+/// Envelopes only record the id, so a dictionary must never change once stored under it.
+pub trait DictionaryProvider: Send + Sync {
+    /// `Ok(None)` when the provider has no dictionary with this id.
+    fn dictionary(&self, id: u32) -> Result<Option<Dictionary>, DecodeError>;
+}
+
+/// Dictionaries compiled into the binary.
+#[derive(Debug, Clone, Copy)]
+pub struct StaticDictionaryProvider {
+    dictionaries: &'static [(u32, &'static [u8])],
+}
+
+impl StaticDictionaryProvider {
+    pub const fn new(dictionaries: &'static [(u32, &'static [u8])]) -> Self {
+        Self { dictionaries }
+    }
+}
+
+impl DictionaryProvider for StaticDictionaryProvider {
+    fn dictionary(&self, id: u32) -> Result<Option<Dictionary>, DecodeError> {
+        Ok(self
+            .dictionaries
+            .iter()
+            .find(|(dictionary_id, _)| *dictionary_id == id)
+            .map(|(_, dictionary)| Dictionary::from(*dictionary)))
+    }
+}
+
+/// Trains a zstd dictionary of at most `max_size` bytes from sample payloads.
+pub fn train_zstd_dictionary<S: AsRef<[u8]>>(
+    samples: &[S],
+    max_size: usize,
+) -> Result<Vec<u8>, DecodeError> {
+    if samples.is_empty() {
+        return Err(DecodeError::InvalidDictionary(
+            "no samples to train on".to_string(),
+        ));
+    }
+    zstd::dict::from_samples(samples, max_size)
+        .map_err(|err| DecodeError::InvalidDictionary(format!("training failed: {err}")))
+}
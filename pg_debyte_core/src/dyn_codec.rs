@@ -0,0 +1,262 @@
+use crate::codec::{BincodeCodec, CborCodec, Codec, RmpCodec};
+use crate::error::DecodeError;
+use crate::types::{DecodeLimits, EncodeLimits};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+/// Callback handed the codec's deserializer by [`DynCodec::decode_erased`].
+pub type ErasedVisit<'a> =
+    dyn FnMut(&mut dyn erased_serde::Deserializer<'_>) -> Result<(), erased_serde::Error> + 'a;
+
+/// Object-safe form of [`Codec`], so a [`Registry`](crate::Registry) can hand out
+/// codecs by id and a decoder entry can use whichever one an envelope names.
+pub trait DynCodec: Send + Sync {
+    fn id(&self) -> u16;
+    fn decode_erased(
+        &self,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+        visit: &mut ErasedVisit<'_>,
+    ) -> Result<(), DecodeError>;
+    fn encode_erased(
+        &self,
+        value: &dyn erased_serde::Serialize,
+        limits: &EncodeLimits,
+    ) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Decodes a `T` with a type-erased codec.
+pub fn decode_dyn<T: DeserializeOwned>(
+    codec: &dyn DynCodec,
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<T, DecodeError> {
+    let mut value = None;
+    codec.decode_erased(bytes, limits, &mut |deserializer| {
+        value = Some(erased_serde::deserialize::<T>(deserializer)?);
+        Ok(())
+    })?;
+    value.ok_or(DecodeError::Serde("codec produced no value".to_string()))
+}
+
+struct ErasedSeed<'a, 'b>(&'a mut ErasedVisit<'b>);
+
+impl<'de> DeserializeSeed<'de> for ErasedSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.0)(&mut erased).map_err(de::Error::custom)
+    }
+}
+
+macro_rules! impl_dyn_codec {
+    ($(#[$attr:meta])* impl $(<const $param:ident: usize>)? for $codec:ty) => {
+        $(#[$attr])*
+        impl$(<const $param: usize>)? DynCodec for $codec {
+            fn id(&self) -> u16 {
+                Codec::id(self)
+            }
+
+            fn decode_erased(
+                &self,
+                bytes: &[u8],
+                limits: &DecodeLimits,
+                visit: &mut ErasedVisit<'_>,
+            ) -> Result<(), DecodeError> {
+                self.decode_seed(ErasedSeed(visit), bytes, limits)
+            }
+
+            fn encode_erased(
+                &self,
+                value: &dyn erased_serde::Serialize,
+                limits: &EncodeLimits,
+            ) -> Result<Vec<u8>, DecodeError> {
+                Codec::encode(self, &value, limits)
+            }
+        }
+    };
+}
+
+impl_dyn_codec!(impl for BincodeCodec);
+impl_dyn_codec!(impl for RmpCodec);
+impl_dyn_codec!(
+    #[cfg(feature = "bincode2")]
+    impl<const BYTE_LIMIT: usize> for crate::codec::Bincode2Codec<BYTE_LIMIT>
+);
+impl_dyn_codec!(
+    #[cfg(feature = "postcard")]
+    impl for crate::codec::PostcardCodec
+);
+
+/// ciborium only decodes owned types from a reader, so seeds go through an
+/// intermediate `ciborium::Value`.
+impl DynCodec for CborCodec {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode_erased(
+        &self,
+        bytes: &[u8],
+        limits: &DecodeLimits,
+        visit: &mut ErasedVisit<'_>,
+    ) -> Result<(), DecodeError> {
+        let value: ciborium::Value = self.decode(bytes, limits)?;
+        ErasedSeed(visit)
+            .deserialize(CborDeserializer(value))
+            .map_err(|err| DecodeError::Cbor(err.to_string()))
+    }
+
+    fn encode_erased(
+        &self,
+        value: &dyn erased_serde::Serialize,
+        limits: &EncodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        self.encode(&value, limits)
+    }
+}
+
+/// Replays a decoded `ciborium::Value` the way ciborium's streaming deserializer would.
+struct CborDeserializer(ciborium::Value);
+
+impl<'de> de::Deserializer<'de> for CborDeserializer {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        use ciborium::Value;
+
+        match self.0 {
+            Value::Integer(int) => {
+                let int = i128::from(int);
+                if let Ok(int) = u64::try_from(int) {
+                    visitor.visit_u64(int)
+                } else if let Ok(int) = i64::try_from(int) {
+                    visitor.visit_i64(int)
+                } else {
+                    visitor.visit_i128(int)
+                }
+            }
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            Value::Float(float) => visitor.visit_f64(float),
+            Value::Text(text) => visitor.visit_string(text),
+            Value::Bool(flag) => visitor.visit_bool(flag),
+            Value::Null => visitor.visit_unit(),
+            Value::Tag(_, inner) => CborDeserializer(*inner).deserialize_any(visitor),
+            Value::Array(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter().map(CborDeserializer));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Map(entries) => {
+                let mut map = MapDeserializer::new(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (CborDeserializer(key), CborDeserializer(value))),
+                );
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            other => Err(de::Error::custom(format!(
+                "unsupported cbor value: {other:?}"
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            ciborium::Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            ciborium::Value::Text(variant) => visitor.visit_enum(variant.into_deserializer()),
+            ciborium::Value::Map(mut entries) if entries.len() == 1 => {
+                let (variant, value) = entries.remove(0);
+                visitor.visit_enum(CborEnum { variant, value })
+            }
+            _ => Err(de::Error::custom("expected cbor enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, de::value::Error> for CborDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+struct CborEnum {
+    variant: ciborium::Value,
+    value: ciborium::Value,
+}
+
+impl<'de> de::EnumAccess<'de> for CborEnum {
+    type Error = de::value::Error;
+    type Variant = CborDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(CborDeserializer(self.variant))?;
+        Ok((variant, CborDeserializer(self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for CborDeserializer {
+    type Error = de::value::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
@@ -1,10 +1,11 @@
-use crate::action::ActionSpec;
-use crate::codec::Codec;
-use crate::envelope::build_envelope;
+use crate::action::{decode_actions_in, encode_actions_in, ActionContext, ActionSpec};
+use crate::codec::PayloadEncoder;
+use crate::compact::{try_parse_compact, CompactEnvelopeBuilder};
+use crate::envelope::{try_parse, ActionHeaders, EnvelopeBuilder, ParsedEnvelope};
 use crate::error::DecodeError;
+use crate::metadata::EnvelopeMetadata;
 use crate::registry::Registry;
-use crate::types::{EncodeLimits, TypeKey};
-use serde::Serialize;
+use crate::types::{DecodeLimits, EncodeLimits, TypeKey};
 
 pub fn encode_to_envelope<T, C>(
     value: &T,
@@ -15,15 +16,161 @@ pub fn encode_to_envelope<T, C>(
     limits: &EncodeLimits,
 ) -> Result<Vec<u8>, DecodeError>
 where
-    T: Serialize,
-    C: Codec,
+    C: PayloadEncoder<T>,
 {
-    let mut payload = codec.encode(value, limits)?;
-    for action in actions {
-        let handler = registry
+    let builder = EnvelopeBuilder::new(key, codec.codec_id()).actions(actions);
+    builder.validate()?;
+    let (payload, actions) = encode_payload(value, codec, key, actions, registry, limits)?;
+    builder.actions(&actions).build(&payload)
+}
+
+pub fn encode_to_envelope_with_metadata<T, C>(
+    value: &T,
+    codec: &C,
+    key: TypeKey,
+    actions: &[ActionSpec],
+    metadata: &EnvelopeMetadata,
+    registry: &dyn Registry,
+    limits: &EncodeLimits,
+) -> Result<Vec<u8>, DecodeError>
+where
+    C: PayloadEncoder<T>,
+{
+    let builder = EnvelopeBuilder::new(key, codec.codec_id())
+        .actions(actions)
+        .metadata(metadata);
+    builder.validate()?;
+    let (payload, actions) = encode_payload(value, codec, key, actions, registry, limits)?;
+    builder.actions(&actions).build(&payload)
+}
+
+pub fn encode_to_compact_envelope<T, C>(
+    value: &T,
+    codec: &C,
+    key: TypeKey,
+    actions: &[ActionSpec],
+    registry: &dyn Registry,
+    limits: &EncodeLimits,
+) -> Result<Vec<u8>, DecodeError>
+where
+    C: PayloadEncoder<T>,
+{
+    let builder =
+        CompactEnvelopeBuilder::for_key(registry, key, codec.codec_id())?.actions(actions);
+    builder.validate()?;
+    let (payload, actions) = encode_payload(value, codec, key, actions, registry, limits)?;
+    builder.actions(&actions).build(&payload)
+}
+
+/// Replaces the actions of an existing envelope without running its codec.
+/// The type key, codec id and metadata are carried over unchanged.
+pub fn rewrap_envelope(
+    input: &[u8],
+    actions: &[ActionSpec],
+    registry: &dyn Registry,
+    decode_limits: &DecodeLimits,
+    encode_limits: &EncodeLimits,
+) -> Result<Vec<u8>, DecodeError> {
+    if let ParsedEnvelope::Envelope(view) = try_parse(input)? {
+        let metadata = EnvelopeMetadata::from(view.metadata);
+        let builder = EnvelopeBuilder::new(view.key, view.codec_id)
+            .actions(actions)
+            .metadata(&metadata);
+        builder.validate()?;
+        let context = ActionContext::new(registry)
+            .with_key(view.key)
+            .with_codec_id(view.codec_id);
+        let payload = decode_actions_in(&context, view.actions, view.payload, decode_limits)?;
+        let (payload, actions) =
+            encode_actions_in(&context, actions, payload.into_owned(), encode_limits)?;
+        return builder.actions(&actions).build(&payload);
+    }
+    if let Some(view) = try_parse_compact(input)? {
+        let builder = CompactEnvelopeBuilder::new(view.alias, view.schema_version, view.codec_id)
+            .actions(actions);
+        builder.validate()?;
+        // Unknown aliases still rewrap, just without a key for keyed actions to bind.
+        let context = match view.resolve_key(registry) {
+            Ok(key) => ActionContext::new(registry).with_key(key),
+            Err(_) => ActionContext::new(registry),
+        }
+        .with_codec_id(view.codec_id);
+        let payload = decode_actions_in(&context, view.actions, view.payload, decode_limits)?;
+        let (payload, actions) =
+            encode_actions_in(&context, actions, payload.into_owned(), encode_limits)?;
+        return builder.actions(&actions).build(&payload);
+    }
+    Err(DecodeError::BadEnvelope("no envelope"))
+}
+
+/// Re-wraps the data keys of an envelope's key-wrapping actions (see
+/// [`ByteAction::rotate_key`](crate::action::ByteAction::rotate_key)) under `new_key_id`.
+/// The payload is copied as is; only the action params and checksum change.
+pub fn rotate_envelope_key(
+    input: &[u8],
+    new_key_id: u32,
+    registry: &dyn Registry,
+) -> Result<Vec<u8>, DecodeError> {
+    if let ParsedEnvelope::Envelope(view) = try_parse(input)? {
+        let context = ActionContext::new(registry).with_key(view.key);
+        let actions = rotate_actions(&context, view.actions, new_key_id)?;
+        let metadata = EnvelopeMetadata::from(view.metadata);
+        return EnvelopeBuilder::new(view.key, view.codec_id)
+            .actions(&actions)
+            .metadata(&metadata)
+            .build(view.payload);
+    }
+    if let Some(view) = try_parse_compact(input)? {
+        let context = ActionContext::new(registry).with_key(view.resolve_key(registry)?);
+        let actions = rotate_actions(&context, view.actions, new_key_id)?;
+        return CompactEnvelopeBuilder::new(view.alias, view.schema_version, view.codec_id)
+            .actions(&actions)
+            .build(view.payload);
+    }
+    Err(DecodeError::BadEnvelope("no envelope"))
+}
+
+fn rotate_actions(
+    context: &ActionContext<'_>,
+    actions: ActionHeaders<'_>,
+    new_key_id: u32,
+) -> Result<Vec<ActionSpec>, DecodeError> {
+    let mut rotated = false;
+    let mut specs = Vec::with_capacity(actions.len());
+    for action in actions.iter() {
+        let handler = context
+            .registry
             .lookup_action(action.id)
             .ok_or(DecodeError::UnknownAction(action.id))?;
-        payload = handler.encode(&payload, limits, &action.params)?;
+        let params = match handler.rotate_key(context, action.params, new_key_id)? {
+            Some(params) => {
+                rotated = true;
+                params
+            }
+            None => action.params.to_vec(),
+        };
+        specs.push(ActionSpec::new(action.id, action.flags, params));
     }
-    Ok(build_envelope(key, codec.id(), actions, &payload))
+    if !rotated {
+        return Err(DecodeError::BadEnvelope("no key-wrapping action to rotate"));
+    }
+    Ok(specs)
+}
+
+fn encode_payload<T, C>(
+    value: &T,
+    codec: &C,
+    key: TypeKey,
+    actions: &[ActionSpec],
+    registry: &dyn Registry,
+    limits: &EncodeLimits,
+) -> Result<(Vec<u8>, Vec<ActionSpec>), DecodeError>
+where
+    C: PayloadEncoder<T>,
+{
+    let payload = codec.encode_payload(value, limits)?;
+    let context = ActionContext::new(registry)
+        .with_key(key)
+        .with_codec_id(codec.codec_id());
+    encode_actions_in(&context, actions, payload, limits)
 }
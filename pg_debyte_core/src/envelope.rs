@@ -1,17 +1,29 @@
 use crate::action::ActionSpec;
 use crate::error::DecodeError;
+use crate::metadata::{EnvelopeMetadata, MetadataView};
 use crate::types::TypeKey;
+use crate::varint;
+use std::io::Write;
 use uuid::Uuid;
 
-const MAGIC: &[u8; 8] = b"PGDEBYTE";
-const ENVELOPE_VERSION: u8 = 1;
+pub const MAGIC: &[u8; 8] = b"PGDEBYTE";
+const ENVELOPE_VERSION_V1: u8 = 1;
+const ENVELOPE_VERSION_V2: u8 = 2;
+const ENVELOPE_VERSION_V3: u8 = 3;
 const MIN_HEADER_LEN: usize = 8 + 1 + 16 + 2 + 2 + 1;
+const CHECKSUM_LEN: usize = 4;
+
+pub const CONFLUENT_MAGIC: u8 = 0;
+const CONFLUENT_HEADER_LEN: usize = 1 + 4;
 
 #[derive(Debug)]
 pub struct EnvelopeView<'a> {
+    pub version: u8,
     pub key: TypeKey,
     pub codec_id: u16,
-    pub actions: Vec<ActionSpec>,
+    pub actions: ActionHeaders<'a>,
+    pub metadata: MetadataView<'a>,
+    pub checksum: Option<u32>,
     pub payload: &'a [u8],
 }
 
@@ -21,7 +33,131 @@ pub enum ParsedEnvelope<'a> {
     Envelope(EnvelopeView<'a>),
 }
 
+/// Confluent Schema Registry wire format: magic byte 0, big-endian schema id, payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfluentView<'a> {
+    pub schema_id: u32,
+    pub payload: &'a [u8],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionHeader<'a> {
+    pub id: u16,
+    pub flags: u8,
+    pub params: &'a [u8],
+}
+
+impl From<ActionHeader<'_>> for ActionSpec {
+    fn from(header: ActionHeader<'_>) -> Self {
+        ActionSpec::new(header.id, header.flags, header.params.to_vec())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HeaderLayout {
+    Fixed,
+    Varint,
+}
+
+/// Action headers borrowed from an already validated envelope.
+#[derive(Debug, Clone, Copy)]
+pub struct ActionHeaders<'a> {
+    count: u8,
+    layout: HeaderLayout,
+    bytes: &'a [u8],
+}
+
+impl<'a> ActionHeaders<'a> {
+    /// Validates `count` headers at the start of `input` and borrows them.
+    pub(crate) fn parse(
+        input: &'a [u8],
+        count: u8,
+        layout: HeaderLayout,
+    ) -> Result<Self, DecodeError> {
+        let mut offset = 0;
+        for _ in 0..count {
+            let (_, len) = read_action_header(&input[offset..], layout)?;
+            offset += len;
+        }
+        Ok(Self {
+            count,
+            layout,
+            bytes: &input[..offset],
+        })
+    }
+
+    /// Length of the encoded headers in bytes.
+    pub(crate) fn encoded_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn iter(&self) -> ActionHeaderIter<'a> {
+        ActionHeaderIter {
+            remaining: self.count,
+            layout: self.layout,
+            bytes: self.bytes,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<ActionHeader<'a>> {
+        self.iter().nth(index)
+    }
+}
+
+impl<'a> IntoIterator for ActionHeaders<'a> {
+    type Item = ActionHeader<'a>;
+    type IntoIter = ActionHeaderIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActionHeaderIter<'a> {
+    remaining: u8,
+    layout: HeaderLayout,
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for ActionHeaderIter<'a> {
+    type Item = ActionHeader<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let (header, len) = read_action_header(self.bytes, self.layout).ok()?;
+        self.bytes = &self.bytes[len..];
+        self.remaining -= 1;
+        Some(header)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl ExactSizeIterator for ActionHeaderIter<'_> {}
+
 pub fn try_parse(input: &[u8]) -> Result<ParsedEnvelope<'_>, DecodeError> {
+    parse(input, true)
+}
+
+/// Parses the header without verifying the v2+ checksum, e.g. to inspect a corrupted row.
+pub fn try_parse_unverified(input: &[u8]) -> Result<ParsedEnvelope<'_>, DecodeError> {
+    parse(input, false)
+}
+
+fn parse(input: &[u8], verify_checksum: bool) -> Result<ParsedEnvelope<'_>, DecodeError> {
     if input.len() < MIN_HEADER_LEN {
         return Ok(ParsedEnvelope::None);
     }
@@ -32,7 +168,10 @@ pub fn try_parse(input: &[u8]) -> Result<ParsedEnvelope<'_>, DecodeError> {
     let mut offset = MAGIC.len();
     let envelope_version = input[offset];
     offset += 1;
-    if envelope_version != ENVELOPE_VERSION {
+    if !matches!(
+        envelope_version,
+        ENVELOPE_VERSION_V1 | ENVELOPE_VERSION_V2 | ENVELOPE_VERSION_V3
+    ) {
         return Err(DecodeError::BadEnvelope("unsupported envelope version"));
     }
 
@@ -60,31 +199,41 @@ pub fn try_parse(input: &[u8]) -> Result<ParsedEnvelope<'_>, DecodeError> {
     let actions_count = input[offset];
     offset += 1;
 
-    let mut actions = Vec::with_capacity(actions_count as usize);
-    for _ in 0..actions_count {
-        if input.len() < offset + 2 + 1 + 2 {
-            return Err(DecodeError::BadEnvelope("action header out of bounds"));
+    let actions = ActionHeaders::parse(&input[offset..], actions_count, HeaderLayout::Fixed)?;
+    offset += actions.encoded_len();
+
+    let mut metadata = MetadataView::default();
+    if envelope_version == ENVELOPE_VERSION_V3 {
+        if input.len() < offset + 2 {
+            return Err(DecodeError::BadEnvelope("metadata header out of bounds"));
         }
-        let action_id = u16::from_le_bytes(
-            input[offset..offset + 2]
-                .try_into()
-                .map_err(|_| DecodeError::BadEnvelope("invalid action_id length"))?,
-        );
+        let metadata_len = u16::from_le_bytes([input[offset], input[offset + 1]]) as usize;
         offset += 2;
-        let flags = input[offset];
-        offset += 1;
-        let params_len = u16::from_le_bytes(
-            input[offset..offset + 2]
+        if input.len() < offset + metadata_len {
+            return Err(DecodeError::BadEnvelope("metadata out of bounds"));
+        }
+        metadata = MetadataView::parse(&input[offset..offset + metadata_len])?;
+        offset += metadata_len;
+    }
+
+    let mut stored_checksum = None;
+    if envelope_version >= ENVELOPE_VERSION_V2 {
+        if input.len() < offset + CHECKSUM_LEN {
+            return Err(DecodeError::BadEnvelope("checksum out of bounds"));
+        }
+        let expected = u32::from_le_bytes(
+            input[offset..offset + CHECKSUM_LEN]
                 .try_into()
-                .map_err(|_| DecodeError::BadEnvelope("invalid params_len length"))?,
-        ) as usize;
-        offset += 2;
-        if input.len() < offset + params_len {
-            return Err(DecodeError::BadEnvelope("params out of bounds"));
+                .map_err(|_| DecodeError::BadEnvelope("invalid checksum length"))?,
+        );
+        if verify_checksum {
+            let actual = checksum(&input[..offset], &input[offset + CHECKSUM_LEN..]);
+            if expected != actual {
+                return Err(DecodeError::ChecksumMismatch { expected, actual });
+            }
         }
-        let params = input[offset..offset + params_len].to_vec();
-        offset += params_len;
-        actions.push(ActionSpec::new(action_id, flags, params));
+        stored_checksum = Some(expected);
+        offset += CHECKSUM_LEN;
     }
 
     if input.len() < offset {
@@ -98,32 +247,214 @@ pub fn try_parse(input: &[u8]) -> Result<ParsedEnvelope<'_>, DecodeError> {
     };
 
     Ok(ParsedEnvelope::Envelope(EnvelopeView {
+        version: envelope_version,
         key,
         codec_id,
         actions,
+        metadata,
+        checksum: stored_checksum,
         payload,
     }))
 }
 
+/// Any input of at least five bytes starting with 0 matches, so only trust the frame once
+/// its schema id maps to a registered type. Format-specific framing after the schema id
+/// (protobuf message indexes) is left in `payload`; see
+/// [`DecoderEntry::strip_confluent_framing`](crate::DecoderEntry::strip_confluent_framing).
+pub fn try_parse_confluent(input: &[u8]) -> Option<ConfluentView<'_>> {
+    if input.len() < CONFLUENT_HEADER_LEN || input[0] != CONFLUENT_MAGIC {
+        return None;
+    }
+    let schema_id = u32::from_be_bytes([input[1], input[2], input[3], input[4]]);
+    Some(ConfluentView {
+        schema_id,
+        payload: &input[CONFLUENT_HEADER_LEN..],
+    })
+}
+
+fn read_action_header(
+    input: &[u8],
+    layout: HeaderLayout,
+) -> Result<(ActionHeader<'_>, usize), DecodeError> {
+    match layout {
+        HeaderLayout::Fixed => read_fixed_action_header(input),
+        HeaderLayout::Varint => read_varint_action_header(input),
+    }
+}
+
+fn read_fixed_action_header(input: &[u8]) -> Result<(ActionHeader<'_>, usize), DecodeError> {
+    if input.len() < 2 + 1 + 2 {
+        return Err(DecodeError::BadEnvelope("action header out of bounds"));
+    }
+    let id = u16::from_le_bytes([input[0], input[1]]);
+    let flags = input[2];
+    let params_len = u16::from_le_bytes([input[3], input[4]]) as usize;
+    let offset = 2 + 1 + 2;
+    if input.len() < offset + params_len {
+        return Err(DecodeError::BadEnvelope("params out of bounds"));
+    }
+    let header = ActionHeader {
+        id,
+        flags,
+        params: &input[offset..offset + params_len],
+    };
+    Ok((header, offset + params_len))
+}
+
+fn read_varint_action_header(input: &[u8]) -> Result<(ActionHeader<'_>, usize), DecodeError> {
+    let (id, mut offset) = varint::read_u16(input)?;
+    let flags = *input
+        .get(offset)
+        .ok_or(DecodeError::BadEnvelope("action header out of bounds"))?;
+    offset += 1;
+    let (params_len, len) = varint::read(&input[offset..])?;
+    offset += len;
+    let params_end = usize::try_from(params_len)
+        .ok()
+        .and_then(|params_len| offset.checked_add(params_len))
+        .filter(|end| *end <= input.len())
+        .ok_or(DecodeError::BadEnvelope("params out of bounds"))?;
+    let header = ActionHeader {
+        id,
+        flags,
+        params: &input[offset..params_end],
+    };
+    Ok((header, params_end))
+}
+
+pub const MAX_ACTIONS: usize = u8::MAX as usize;
+pub const MAX_ACTION_PARAMS_BYTES: usize = u16::MAX as usize;
+
+#[derive(Debug, Clone, Copy)]
+pub struct EnvelopeBuilder<'a> {
+    key: TypeKey,
+    codec_id: u16,
+    actions: &'a [ActionSpec],
+    metadata: Option<&'a EnvelopeMetadata>,
+}
+
+impl<'a> EnvelopeBuilder<'a> {
+    pub fn new(key: TypeKey, codec_id: u16) -> Self {
+        Self {
+            key,
+            codec_id,
+            actions: &[],
+            metadata: None,
+        }
+    }
+
+    pub fn actions(mut self, actions: &'a [ActionSpec]) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    pub fn metadata(mut self, metadata: &'a EnvelopeMetadata) -> Self {
+        self.metadata = Some(metadata).filter(|metadata| !metadata.is_empty());
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), DecodeError> {
+        validate_actions(self.actions)?;
+        if let Some(metadata) = self.metadata {
+            metadata.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Header bytes up to (not including) the checksum.
+    pub fn header(&self) -> Result<Vec<u8>, DecodeError> {
+        self.validate()?;
+        let version = match self.metadata {
+            Some(_) => ENVELOPE_VERSION_V3,
+            None => ENVELOPE_VERSION_V2,
+        };
+        let mut output = Vec::new();
+        output.extend_from_slice(MAGIC);
+        output.push(version);
+        output.extend_from_slice(self.key.type_id.as_bytes());
+        output.extend_from_slice(&self.key.schema_version.to_le_bytes());
+        output.extend_from_slice(&self.codec_id.to_le_bytes());
+        output.push(self.actions.len() as u8);
+        for action in self.actions {
+            output.extend_from_slice(&action.id.to_le_bytes());
+            output.push(action.flags);
+            output.extend_from_slice(&(action.params.len() as u16).to_le_bytes());
+            output.extend_from_slice(&action.params);
+        }
+        if let Some(metadata) = self.metadata {
+            metadata.write_to(&mut output)?;
+        }
+        Ok(output)
+    }
+
+    pub fn build(&self, payload: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let mut output = Vec::new();
+        self.build_into(&mut output, payload)?;
+        Ok(output)
+    }
+
+    /// Appends the envelope to `output`, reserving space for the payload up front.
+    pub fn build_into(&self, output: &mut Vec<u8>, payload: &[u8]) -> Result<(), DecodeError> {
+        let header = self.header()?;
+        let checksum = checksum(&header, payload);
+        output.reserve(header.len() + CHECKSUM_LEN + payload.len());
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&checksum.to_le_bytes());
+        output.extend_from_slice(payload);
+        Ok(())
+    }
+
+    /// Streams the envelope into `writer`; returns the number of bytes written.
+    pub fn write_to<W: Write>(&self, writer: &mut W, payload: &[u8]) -> Result<usize, DecodeError> {
+        let header = self.header()?;
+        let checksum = checksum(&header, payload);
+        writer.write_all(&header)?;
+        writer.write_all(&checksum.to_le_bytes())?;
+        writer.write_all(payload)?;
+        Ok(header.len() + CHECKSUM_LEN + payload.len())
+    }
+}
+
+pub(crate) fn validate_actions(actions: &[ActionSpec]) -> Result<(), DecodeError> {
+    if actions.len() > MAX_ACTIONS {
+        return Err(DecodeError::TooManyActions(actions.len()));
+    }
+    for action in actions {
+        if action.params.len() > MAX_ACTION_PARAMS_BYTES {
+            return Err(DecodeError::ActionParamsTooLarge {
+                id: action.id,
+                len: action.params.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
 pub fn build_envelope(
     key: TypeKey,
     codec_id: u16,
     actions: &[ActionSpec],
     payload: &[u8],
-) -> Vec<u8> {
-    let mut output = Vec::new();
-    output.extend_from_slice(MAGIC);
-    output.push(ENVELOPE_VERSION);
-    output.extend_from_slice(key.type_id.as_bytes());
-    output.extend_from_slice(&key.schema_version.to_le_bytes());
-    output.extend_from_slice(&codec_id.to_le_bytes());
-    output.push(actions.len() as u8);
-    for action in actions {
-        output.extend_from_slice(&action.id.to_le_bytes());
-        output.push(action.flags);
-        output.extend_from_slice(&(action.params.len() as u16).to_le_bytes());
-        output.extend_from_slice(&action.params);
-    }
-    output.extend_from_slice(payload);
-    output
+) -> Result<Vec<u8>, DecodeError> {
+    EnvelopeBuilder::new(key, codec_id)
+        .actions(actions)
+        .build(payload)
+}
+
+pub fn build_envelope_with_metadata(
+    key: TypeKey,
+    codec_id: u16,
+    actions: &[ActionSpec],
+    metadata: &EnvelopeMetadata,
+    payload: &[u8],
+) -> Result<Vec<u8>, DecodeError> {
+    EnvelopeBuilder::new(key, codec_id)
+        .actions(actions)
+        .metadata(metadata)
+        .build(payload)
+}
+
+/// CRC32C over the header (everything before the checksum field) and the payload.
+fn checksum(header: &[u8], payload: &[u8]) -> u32 {
+    crc32c::crc32c_append(crc32c::crc32c(header), payload)
 }
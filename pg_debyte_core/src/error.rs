@@ -5,12 +5,36 @@ use thiserror::Error;
 pub enum DecodeError {
     #[error("bad envelope: {0}")]
     BadEnvelope(&'static str),
+    #[error("envelope checksum mismatch: expected={expected:#010x} actual={actual:#010x}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+    #[error("signature verification failed for key {key_id}")]
+    SignatureInvalid { key_id: u32 },
+    #[error("too many actions: {0} (max 255)")]
+    TooManyActions(usize),
+    #[error("action {id} params too large: {len} bytes (max 65535)")]
+    ActionParamsTooLarge { id: u16, len: usize },
+    #[error("invalid params for action {id}: {reason}")]
+    InvalidActionParams { id: u16, reason: String },
     #[error("unknown type: {0:?}")]
     UnknownType(TypeKey),
+    #[error("unknown type alias: {0}")]
+    UnknownTypeAlias(u32),
+    #[error("unknown schema registry id: {0}")]
+    UnknownSchemaId(u32),
     #[error("unknown action id: {0}")]
     UnknownAction(u16),
     #[error("unknown codec id: {0}")]
     UnknownCodec(u16),
+    #[error("required action {0} missing from envelope")]
+    MissingAction(u16),
+    #[error("unknown key id: {0}")]
+    UnknownKey(u32),
+    #[error("invalid key: {0}")]
+    InvalidKey(String),
+    #[error("unknown dictionary id: {0}")]
+    UnknownDictionary(u32),
+    #[error("invalid dictionary: {0}")]
+    InvalidDictionary(String),
     #[error("limit exceeded for {context}: limit={limit} actual={actual}")]
     LimitExceeded {
         context: &'static str,
@@ -21,6 +45,28 @@ pub enum DecodeError {
     Serde(String),
     #[error("bincode error: {0}")]
     Bincode(String),
+    #[error("aead error: {0}")]
+    Aead(String),
+    #[error("avro error: {0}")]
+    Avro(String),
+    #[error("borsh error: {0}")]
+    Borsh(String),
+    #[error("brotli error: {0}")]
+    Brotli(String),
+    #[error("cbor error: {0}")]
+    Cbor(String),
+    #[error("deflate error: {0}")]
+    Deflate(String),
+    #[error("lz4 error: {0}")]
+    Lz4(String),
+    #[error("messagepack error: {0}")]
+    MessagePack(String),
+    #[error("postcard error: {0}")]
+    Postcard(String),
+    #[error("protobuf error: {0}")]
+    Protobuf(String),
+    #[error("snappy error: {0}")]
+    Snappy(String),
     #[error("zstd error: {0}")]
     Zstd(String),
     #[error("json error: {0}")]
@@ -0,0 +1,57 @@
+use crate::codec::LimitedWriter;
+use crate::error::DecodeError;
+use crate::types::DecodeLimits;
+use serde::Serialize;
+use std::io::Write;
+
+/// Serializes `value` to JSON text, stopping as soon as `max_json_bytes` is exceeded.
+pub fn to_json_bytes<T: Serialize + ?Sized>(
+    value: &T,
+    limits: &DecodeLimits,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut writer = LimitedWriter::new(limits.max_json_bytes);
+    let result = serde_json::to_writer(&mut writer, value);
+    writer.check("json_bytes")?;
+    result.map_err(|err| DecodeError::Serde(err.to_string()))?;
+    Ok(writer.into_inner())
+}
+
+/// Checks the JSON text length of `value` against `max_json_bytes` without buffering it.
+pub fn ensure_json_limit<T: Serialize + ?Sized>(
+    value: &T,
+    limits: &DecodeLimits,
+) -> Result<usize, DecodeError> {
+    let mut counter = CountingWriter {
+        written: 0,
+        limit: limits.max_json_bytes,
+    };
+    let result = serde_json::to_writer(&mut counter, value);
+    if counter.written > counter.limit {
+        return Err(DecodeError::LimitExceeded {
+            context: "json_bytes",
+            limit: counter.limit,
+            actual: counter.written,
+        });
+    }
+    result.map_err(|err| DecodeError::Serde(err.to_string()))?;
+    Ok(counter.written)
+}
+
+struct CountingWriter {
+    written: usize,
+    limit: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written = self.written.saturating_add(buf.len());
+        if self.written > self.limit {
+            return Err(std::io::Error::other("json limit exceeded"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use crate::error::DecodeError;
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
+
+/// Key material handed to keyed actions; wiped on drop.
+pub type KeyBytes = Zeroizing<Vec<u8>>;
+
+/// Supplies keys by id to keyed actions (see [`Registry::key_provider`](crate::Registry::key_provider)).
+pub trait KeyProvider: Send + Sync {
+    /// `Ok(None)` when the provider has no key with this id.
+    fn key(&self, key_id: u32) -> Result<Option<KeyBytes>, DecodeError>;
+}
+
+/// Keys compiled into the binary.
+#[derive(Debug, Clone, Copy)]
+pub struct StaticKeyProvider {
+    keys: &'static [(u32, &'static [u8])],
+}
+
+impl StaticKeyProvider {
+    pub const fn new(keys: &'static [(u32, &'static [u8])]) -> Self {
+        Self { keys }
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn key(&self, key_id: u32) -> Result<Option<KeyBytes>, DecodeError> {
+        Ok(self
+            .keys
+            .iter()
+            .find(|(id, _)| *id == key_id)
+            .map(|(_, key)| Zeroizing::new(key.to_vec())))
+    }
+}
+
+type KeyCallback = Box<dyn Fn(u32) -> Option<KeyBytes> + Send + Sync>;
+
+/// Key lookup installed at runtime, e.g. from `_PG_init`.
+pub struct CallbackKeyProvider {
+    callback: OnceLock<KeyCallback>,
+}
+
+impl CallbackKeyProvider {
+    pub const fn new() -> Self {
+        Self {
+            callback: OnceLock::new(),
+        }
+    }
+
+    /// Installs the lookup; only the first call takes effect.
+    pub fn set(&self, callback: impl Fn(u32) -> Option<KeyBytes> + Send + Sync + 'static) {
+        let _ = self.callback.set(Box::new(callback));
+    }
+}
+
+impl Default for CallbackKeyProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyProvider for CallbackKeyProvider {
+    fn key(&self, key_id: u32) -> Result<Option<KeyBytes>, DecodeError> {
+        Ok(self.callback.get().and_then(|callback| callback(key_id)))
+    }
+}
+
+/// Parses `id:hex` entries separated by commas or newlines. `#` starts a comment.
+pub fn parse_keys(text: &str) -> Result<Vec<(u32, KeyBytes)>, DecodeError> {
+    let mut keys = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        for entry in line
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (id, key) = entry
+                .split_once(':')
+                .ok_or_else(|| DecodeError::InvalidKey("expected `id:hex`".to_string()))?;
+            let id = id
+                .trim()
+                .parse::<u32>()
+                .map_err(|err| DecodeError::InvalidKey(format!("bad key id {id:?}: {err}")))?;
+            // The hex error would echo key characters, so it is not included.
+            let key = hex::decode(key.trim())
+                .map_err(|_| DecodeError::InvalidKey(format!("bad hex for key {id}")))?;
+            keys.push((id, Zeroizing::new(key)));
+        }
+    }
+    Ok(keys)
+}
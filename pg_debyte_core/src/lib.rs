@@ -1,15 +1,74 @@
 pub mod action;
+#[cfg(feature = "aead")]
+pub mod aead;
+#[cfg(feature = "avro")]
+pub mod avro;
 pub mod codec;
+pub mod compact;
+pub mod dictionary;
+pub mod dyn_codec;
 pub mod encode;
 pub mod envelope;
 pub mod error;
+pub mod json;
+pub mod keys;
+pub mod metadata;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
 pub mod registry;
+pub mod schemaless;
+#[cfg(feature = "signature")]
+pub mod signature;
+pub mod stream;
 pub mod types;
+mod varint;
 
-pub use action::{ActionSpec, ActionSpecRef, ByteAction, ZstdAction};
-pub use codec::{BincodeCodec, Codec};
-pub use encode::encode_to_envelope;
-pub use envelope::{EnvelopeView, ParsedEnvelope};
+#[cfg(feature = "brotli")]
+pub use action::BrotliAction;
+pub use action::{
+    ensure_required_actions, ActionContext, ActionSpec, ActionSpecRef, ByteAction, ZstdAction,
+};
+#[cfg(feature = "gzip")]
+pub use action::{DeflateAction, GzipAction};
+#[cfg(feature = "lz4")]
+pub use action::{Lz4Action, Lz4Format};
+#[cfg(feature = "snappy")]
+pub use action::{SnappyAction, SnappyFormat};
+#[cfg(feature = "aead")]
+pub use aead::{AeadAction, AeadAlgorithm, EnvelopeEncryptionAction};
+#[cfg(feature = "avro")]
+pub use avro::{AvroDecoderEntry, AvroSchema};
+#[cfg(feature = "bincode2")]
+pub use codec::Bincode2Codec;
+#[cfg(feature = "borsh")]
+pub use codec::BorshCodec;
+#[cfg(feature = "postcard")]
+pub use codec::PostcardCodec;
+pub use codec::{
+    BincodeCodec, BincodeOptions, CborCodec, CborValue, Codec, Endian, IntEncoding, PayloadDecoder,
+    PayloadEncoder, RmpCodec, RmpStructLayout,
+};
+pub use compact::{CompactEnvelopeBuilder, CompactEnvelopeView};
+pub use dictionary::{
+    train_zstd_dictionary, Dictionary, DictionaryProvider, StaticDictionaryProvider,
+};
+pub use dyn_codec::{decode_dyn, DynCodec};
+pub use encode::{
+    encode_to_compact_envelope, encode_to_envelope, encode_to_envelope_with_metadata,
+    rewrap_envelope, rotate_envelope_key,
+};
+pub use envelope::{
+    ActionHeader, ActionHeaders, ConfluentView, EnvelopeBuilder, EnvelopeView, ParsedEnvelope,
+};
 pub use error::DecodeError;
+pub use json::{ensure_json_limit, to_json_bytes};
+pub use keys::{parse_keys, CallbackKeyProvider, KeyBytes, KeyProvider, StaticKeyProvider};
+pub use metadata::{EnvelopeMetadata, MetadataView};
+#[cfg(feature = "protobuf")]
+pub use protobuf::ProtobufDecoderEntry;
 pub use registry::{DecoderEntry, Registry, StaticRegistry, TypedDecoderEntry};
+pub use schemaless::{SchemalessDecoderEntry, SchemalessFormat};
+#[cfg(feature = "signature")]
+pub use signature::{SignatureAction, SignatureAlgorithm};
+pub use stream::{frames, FrameIter, FrameReader};
 pub use types::{DecodeLimits, EncodeLimits, TypeKey};
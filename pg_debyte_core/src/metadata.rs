@@ -0,0 +1,220 @@
+use crate::error::DecodeError;
+use serde_json::{Map, Value};
+
+pub const MAX_METADATA_BYTES: usize = 4096;
+
+pub const TAG_PRODUCER: u8 = 1;
+pub const TAG_CREATED_AT_MS: u8 = 2;
+pub const TAG_TENANT_ID: u8 = 3;
+pub const TAG_TRACE_ID: u8 = 4;
+
+const ENTRY_HEADER_LEN: usize = 1 + 2;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvelopeMetadata {
+    entries: Vec<(u8, Vec<u8>)>,
+}
+
+impl EnvelopeMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn producer(self, producer: impl Into<String>) -> Self {
+        self.entry(TAG_PRODUCER, producer.into().into_bytes())
+    }
+
+    pub fn created_at_ms(self, unix_ms: i64) -> Self {
+        self.entry(TAG_CREATED_AT_MS, unix_ms.to_le_bytes().to_vec())
+    }
+
+    pub fn tenant_id(self, tenant_id: impl Into<String>) -> Self {
+        self.entry(TAG_TENANT_ID, tenant_id.into().into_bytes())
+    }
+
+    pub fn trace_id(self, trace_id: impl Into<String>) -> Self {
+        self.entry(TAG_TRACE_ID, trace_id.into().into_bytes())
+    }
+
+    pub fn entry(mut self, tag: u8, value: impl Into<Vec<u8>>) -> Self {
+        let value = value.into();
+        match self
+            .entries
+            .iter_mut()
+            .find(|(existing, _)| *existing == tag)
+        {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((tag, value)),
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn encoded_len(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(_, value)| ENTRY_HEADER_LEN + value.len())
+            .sum()
+    }
+
+    pub fn validate(&self) -> Result<(), DecodeError> {
+        let len = self.encoded_len();
+        if len > MAX_METADATA_BYTES {
+            return Err(DecodeError::LimitExceeded {
+                context: "metadata_bytes",
+                limit: MAX_METADATA_BYTES,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_to(&self, output: &mut Vec<u8>) -> Result<(), DecodeError> {
+        self.validate()?;
+        output.extend_from_slice(&(self.encoded_len() as u16).to_le_bytes());
+        for (tag, value) in &self.entries {
+            output.push(*tag);
+            output.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            output.extend_from_slice(value);
+        }
+        Ok(())
+    }
+}
+
+impl From<MetadataView<'_>> for EnvelopeMetadata {
+    fn from(view: MetadataView<'_>) -> Self {
+        view.iter().fold(Self::new(), |metadata, (tag, value)| {
+            metadata.entry(tag, value)
+        })
+    }
+}
+
+/// Metadata entries borrowed from an already validated envelope.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetadataView<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> MetadataView<'a> {
+    pub(crate) fn parse(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        if bytes.len() > MAX_METADATA_BYTES {
+            return Err(DecodeError::BadEnvelope("metadata too large"));
+        }
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let (_, len) = read_entry(rest)?;
+            rest = &rest[len..];
+        }
+        Ok(Self { bytes })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn iter(&self) -> MetadataIter<'a> {
+        MetadataIter { bytes: self.bytes }
+    }
+
+    pub fn get(&self, tag: u8) -> Option<&'a [u8]> {
+        self.iter()
+            .find(|(entry_tag, _)| *entry_tag == tag)
+            .map(|(_, value)| value)
+    }
+
+    pub fn producer(&self) -> Option<&'a str> {
+        self.get_str(TAG_PRODUCER)
+    }
+
+    pub fn created_at_ms(&self) -> Option<i64> {
+        let value = self.get(TAG_CREATED_AT_MS)?;
+        Some(i64::from_le_bytes(value.try_into().ok()?))
+    }
+
+    pub fn tenant_id(&self) -> Option<&'a str> {
+        self.get_str(TAG_TENANT_ID)
+    }
+
+    pub fn trace_id(&self) -> Option<&'a str> {
+        self.get_str(TAG_TRACE_ID)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        for (tag, value) in self.iter() {
+            let (name, json) = match tag {
+                TAG_PRODUCER => ("producer".to_string(), text_or_hex(value)),
+                TAG_CREATED_AT_MS => match <[u8; 8]>::try_from(value) {
+                    Ok(bytes) => (
+                        "created_at_ms".to_string(),
+                        Value::from(i64::from_le_bytes(bytes)),
+                    ),
+                    Err(_) => ("created_at_ms".to_string(), hex_value(value)),
+                },
+                TAG_TENANT_ID => ("tenant_id".to_string(), text_or_hex(value)),
+                TAG_TRACE_ID => ("trace_id".to_string(), text_or_hex(value)),
+                other => (format!("tag_{other}"), hex_value(value)),
+            };
+            object.insert(name, json);
+        }
+        Value::Object(object)
+    }
+
+    fn get_str(&self, tag: u8) -> Option<&'a str> {
+        std::str::from_utf8(self.get(tag)?).ok()
+    }
+}
+
+impl<'a> IntoIterator for MetadataView<'a> {
+    type Item = (u8, &'a [u8]);
+    type IntoIter = MetadataIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetadataIter<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for MetadataIter<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let (entry, len) = read_entry(self.bytes).ok()?;
+        self.bytes = &self.bytes[len..];
+        Some(entry)
+    }
+}
+
+fn read_entry(input: &[u8]) -> Result<((u8, &[u8]), usize), DecodeError> {
+    if input.len() < ENTRY_HEADER_LEN {
+        return Err(DecodeError::BadEnvelope("metadata entry out of bounds"));
+    }
+    let tag = input[0];
+    let len = u16::from_le_bytes([input[1], input[2]]) as usize;
+    if input.len() < ENTRY_HEADER_LEN + len {
+        return Err(DecodeError::BadEnvelope("metadata value out of bounds"));
+    }
+    let value = &input[ENTRY_HEADER_LEN..ENTRY_HEADER_LEN + len];
+    Ok(((tag, value), ENTRY_HEADER_LEN + len))
+}
+
+fn text_or_hex(value: &[u8]) -> Value {
+    match std::str::from_utf8(value) {
+        Ok(text) => Value::from(text),
+        Err(_) => hex_value(value),
+    }
+}
+
+fn hex_value(value: &[u8]) -> Value {
+    Value::from(hex::encode(value))
+}
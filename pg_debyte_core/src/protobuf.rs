@@ -0,0 +1,165 @@
+use crate::action::ActionSpecRef;
+use crate::error::DecodeError;
+use crate::registry::DecoderEntry;
+use crate::types::{DecodeLimits, TypeKey};
+use crate::varint;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use std::sync::OnceLock;
+
+/// Decoder entry for protobuf messages described by a `FileDescriptorSet`
+/// instead of a Rust type. Output is canonical proto3 JSON.
+pub struct ProtobufDecoderEntry {
+    key: TypeKey,
+    codec_id: u16,
+    descriptor_set: &'static [u8],
+    message_name: &'static str,
+    default_actions: &'static [ActionSpecRef],
+    descriptor: OnceLock<Result<MessageDescriptor, String>>,
+}
+
+impl ProtobufDecoderEntry {
+    /// Uses an embedded descriptor set (e.g. `include_bytes!`), parsed on first decode.
+    pub const fn new(
+        key: TypeKey,
+        codec_id: u16,
+        descriptor_set: &'static [u8],
+        message_name: &'static str,
+        default_actions: &'static [ActionSpecRef],
+    ) -> Self {
+        Self {
+            key,
+            codec_id,
+            descriptor_set,
+            message_name,
+            default_actions,
+            descriptor: OnceLock::new(),
+        }
+    }
+
+    /// Parses descriptor set bytes loaded at runtime.
+    pub fn from_descriptor_set(
+        key: TypeKey,
+        codec_id: u16,
+        descriptor_set: &[u8],
+        message_name: &str,
+        default_actions: &'static [ActionSpecRef],
+    ) -> Result<Self, DecodeError> {
+        let descriptor = load_descriptor(descriptor_set, message_name)?;
+        Ok(Self::from_descriptor(
+            key,
+            codec_id,
+            descriptor,
+            default_actions,
+        ))
+    }
+
+    pub fn from_descriptor(
+        key: TypeKey,
+        codec_id: u16,
+        descriptor: MessageDescriptor,
+        default_actions: &'static [ActionSpecRef],
+    ) -> Self {
+        let entry = Self::new(key, codec_id, &[], "", default_actions);
+        let _ = entry.descriptor.set(Ok(descriptor));
+        entry
+    }
+
+    pub fn descriptor(&self) -> Result<&MessageDescriptor, DecodeError> {
+        self.descriptor
+            .get_or_init(|| {
+                load_descriptor(self.descriptor_set, self.message_name).map_err(|err| match err {
+                    DecodeError::Protobuf(message) => message,
+                    other => other.to_string(),
+                })
+            })
+            .as_ref()
+            .map_err(|err| DecodeError::Protobuf(err.clone()))
+    }
+}
+
+impl DecoderEntry for ProtobufDecoderEntry {
+    fn key(&self) -> TypeKey {
+        self.key
+    }
+
+    fn codec_id(&self) -> u16 {
+        self.codec_id
+    }
+
+    fn default_actions(&self) -> &'static [ActionSpecRef] {
+        self.default_actions
+    }
+
+    fn decode_payload(
+        &self,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<serde_json::Value, DecodeError> {
+        if payload.len() > limits.max_output_bytes {
+            return Err(DecodeError::LimitExceeded {
+                context: "codec_input_bytes",
+                limit: limits.max_output_bytes,
+                actual: payload.len(),
+            });
+        }
+        let message = DynamicMessage::decode(self.descriptor()?.clone(), payload)
+            .map_err(|err| DecodeError::Protobuf(err.to_string()))?;
+        message
+            .serialize_with_options(serde_json::value::Serializer, &SerializeOptions::new())
+            .map_err(|err| DecodeError::Serde(err.to_string()))
+    }
+
+    /// Skips the zigzag-varint message-index path (a lone `0` meaning `[0]`) and checks
+    /// it names this entry's message.
+    fn strip_confluent_framing<'a>(&self, payload: &'a [u8]) -> Result<&'a [u8], DecodeError> {
+        let (count, mut offset) = read_zigzag(payload)?;
+        let indexes = if count == 0 {
+            vec![0]
+        } else {
+            if count < 0 || count as u64 > payload.len() as u64 {
+                return Err(DecodeError::Protobuf(format!(
+                    "invalid message index count {count}"
+                )));
+            }
+            let mut indexes = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let (index, len) = read_zigzag(&payload[offset..])?;
+                indexes.push(index);
+                offset += len;
+            }
+            indexes
+        };
+        let descriptor = self.descriptor()?;
+        // Message paths alternate field numbers and indexes: [4, top, 3, nested, ...].
+        let expected = descriptor.path().iter().skip(1).step_by(2);
+        if !indexes
+            .iter()
+            .copied()
+            .eq(expected.map(|index| i64::from(*index)))
+        {
+            return Err(DecodeError::Protobuf(format!(
+                "message indexes {indexes:?} do not name {}",
+                descriptor.full_name()
+            )));
+        }
+        Ok(&payload[offset..])
+    }
+}
+
+fn read_zigzag(input: &[u8]) -> Result<(i64, usize), DecodeError> {
+    let (value, len) = varint::read(input)?;
+    Ok((((value >> 1) as i64) ^ -((value & 1) as i64), len))
+}
+
+fn load_descriptor(
+    descriptor_set: &[u8],
+    message_name: &str,
+) -> Result<MessageDescriptor, DecodeError> {
+    let pool = DescriptorPool::decode(descriptor_set)
+        .map_err(|err| DecodeError::Protobuf(err.to_string()))?;
+    pool.get_message_by_name(message_name).ok_or_else(|| {
+        DecodeError::Protobuf(format!(
+            "message {message_name} not found in descriptor set"
+        ))
+    })
+}
@@ -1,10 +1,14 @@
 use crate::action::ActionSpecRef;
-use crate::codec::Codec;
+use crate::codec::PayloadDecoder;
+use crate::dictionary::DictionaryProvider;
+use crate::dyn_codec::DynCodec;
 use crate::error::DecodeError;
+use crate::json::to_json_bytes;
+use crate::keys::KeyProvider;
 use crate::types::{DecodeLimits, TypeKey};
-use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::marker::PhantomData;
+use uuid::Uuid;
 
 pub trait DecoderEntry: Send + Sync {
     fn key(&self) -> TypeKey;
@@ -15,12 +19,59 @@ pub trait DecoderEntry: Send + Sync {
         payload: &[u8],
         limits: &DecodeLimits,
     ) -> Result<serde_json::Value, DecodeError>;
+
+    /// Decodes to JSON text bounded by `max_json_bytes`.
+    fn decode_payload_json(
+        &self,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        to_json_bytes(&self.decode_payload(payload, limits)?, limits)
+    }
+
+    /// Action ids every envelope of this type must carry, e.g. a `SignatureAction`;
+    /// envelopes missing one are rejected before any codec runs.
+    fn required_actions(&self) -> &'static [u16] {
+        &[]
+    }
+
+    /// Strips framing a Confluent serializer writes between the schema id and the payload,
+    /// such as protobuf message indexes.
+    fn strip_confluent_framing<'a>(&self, payload: &'a [u8]) -> Result<&'a [u8], DecodeError> {
+        Ok(payload)
+    }
+
+    /// Whether payloads written with `codec_id` can be decoded by this entry.
+    fn accepts_codec(&self, codec_id: u16) -> bool {
+        codec_id == self.codec_id()
+    }
+
+    /// Decodes a payload written with another registered codec.
+    fn decode_payload_with(
+        &self,
+        codec: &dyn DynCodec,
+        _payload: &[u8],
+        _limits: &DecodeLimits,
+    ) -> Result<serde_json::Value, DecodeError> {
+        Err(DecodeError::UnknownCodec(codec.id()))
+    }
+
+    fn decode_payload_json_with(
+        &self,
+        codec: &dyn DynCodec,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        to_json_bytes(&self.decode_payload_with(codec, payload, limits)?, limits)
+    }
 }
 
 pub struct TypedDecoderEntry<T, C> {
     key: TypeKey,
     codec: C,
     default_actions: &'static [ActionSpecRef],
+    required_actions: &'static [u16],
+    registered_codecs: bool,
     _marker: PhantomData<T>,
 }
 
@@ -30,46 +81,134 @@ impl<T, C> TypedDecoderEntry<T, C> {
             key,
             codec,
             default_actions,
+            required_actions: &[],
+            registered_codecs: false,
             _marker: PhantomData,
         }
     }
+
+    /// Also accepts payloads written with any codec from `Registry::lookup_codec`.
+    pub const fn with_registered_codecs(mut self) -> Self {
+        self.registered_codecs = true;
+        self
+    }
+
+    /// Rejects envelopes that do not carry all of `required_actions`.
+    pub const fn with_required_actions(mut self, required_actions: &'static [u16]) -> Self {
+        self.required_actions = required_actions;
+        self
+    }
 }
 
 impl<T, C> DecoderEntry for TypedDecoderEntry<T, C>
 where
-    T: DeserializeOwned + Serialize + Send + Sync,
-    C: Codec + Send + Sync,
+    T: Serialize + Send + Sync,
+    C: PayloadDecoder<T> + Send + Sync,
 {
     fn key(&self) -> TypeKey {
         self.key
     }
 
     fn codec_id(&self) -> u16 {
-        self.codec.id()
+        self.codec.codec_id()
     }
 
     fn default_actions(&self) -> &'static [ActionSpecRef] {
         self.default_actions
     }
 
+    fn required_actions(&self) -> &'static [u16] {
+        self.required_actions
+    }
+
     fn decode_payload(
         &self,
         payload: &[u8],
         limits: &DecodeLimits,
     ) -> Result<serde_json::Value, DecodeError> {
-        let value: T = self.codec.decode(payload, limits)?;
+        let value = self.codec.decode_payload(payload, limits)?;
+        serde_json::to_value(value).map_err(|err| DecodeError::Serde(err.to_string()))
+    }
+
+    /// Serializes the decoded `T` straight into the JSON writer, skipping `serde_json::Value`.
+    fn decode_payload_json(
+        &self,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let value = self.codec.decode_payload(payload, limits)?;
+        to_json_bytes(&value, limits)
+    }
+
+    fn accepts_codec(&self, codec_id: u16) -> bool {
+        self.registered_codecs || codec_id == self.codec.codec_id()
+    }
+
+    fn decode_payload_with(
+        &self,
+        codec: &dyn DynCodec,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<serde_json::Value, DecodeError> {
+        if !self.accepts_codec(codec.id()) {
+            return Err(DecodeError::UnknownCodec(codec.id()));
+        }
+        let value = self.codec.decode_payload_with(codec, payload, limits)?;
         serde_json::to_value(value).map_err(|err| DecodeError::Serde(err.to_string()))
     }
+
+    fn decode_payload_json_with(
+        &self,
+        codec: &dyn DynCodec,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<Vec<u8>, DecodeError> {
+        if !self.accepts_codec(codec.id()) {
+            return Err(DecodeError::UnknownCodec(codec.id()));
+        }
+        let value = self.codec.decode_payload_with(codec, payload, limits)?;
+        to_json_bytes(&value, limits)
+    }
 }
 
 pub trait Registry: Send + Sync {
     fn lookup_decoder(&self, key: TypeKey) -> Option<&'static dyn DecoderEntry>;
     fn lookup_action(&self, id: u16) -> Option<&'static dyn crate::action::ByteAction>;
+    fn lookup_codec(&self, _id: u16) -> Option<&'static dyn DynCodec> {
+        None
+    }
+    fn lookup_schema_id(&self, _schema_id: u32) -> Option<TypeKey> {
+        None
+    }
+    fn lookup_type_alias(&self, _alias: u32) -> Option<Uuid> {
+        None
+    }
+    fn type_alias_for(&self, _type_id: Uuid) -> Option<u32> {
+        None
+    }
+    /// Decoder used for unregistered types carrying `codec_id`, if any.
+    fn lookup_fallback_decoder(&self, _codec_id: u16) -> Option<&'static dyn DecoderEntry> {
+        None
+    }
+    /// Keys for keyed actions such as `AeadAction`.
+    fn key_provider(&self) -> Option<&'static dyn KeyProvider> {
+        None
+    }
+    /// Compression dictionaries referenced by id from action params, e.g. `ZstdAction`.
+    fn dictionary_provider(&self) -> Option<&'static dyn DictionaryProvider> {
+        None
+    }
 }
 
 pub struct StaticRegistry {
     decoders: &'static [&'static dyn DecoderEntry],
     actions: &'static [&'static dyn crate::action::ByteAction],
+    codecs: &'static [&'static dyn DynCodec],
+    schema_ids: &'static [(u32, TypeKey)],
+    type_aliases: &'static [(u32, Uuid)],
+    fallback_decoders: &'static [&'static dyn DecoderEntry],
+    key_provider: Option<&'static dyn KeyProvider>,
+    dictionary_provider: Option<&'static dyn DictionaryProvider>,
 }
 
 impl StaticRegistry {
@@ -77,7 +216,56 @@ impl StaticRegistry {
         decoders: &'static [&'static dyn DecoderEntry],
         actions: &'static [&'static dyn crate::action::ByteAction],
     ) -> Self {
-        Self { decoders, actions }
+        Self {
+            decoders,
+            actions,
+            codecs: &[],
+            schema_ids: &[],
+            type_aliases: &[],
+            fallback_decoders: &[],
+            key_provider: None,
+            dictionary_provider: None,
+        }
+    }
+
+    /// Codecs available by id to entries built `with_registered_codecs`.
+    pub const fn with_codecs(mut self, codecs: &'static [&'static dyn DynCodec]) -> Self {
+        self.codecs = codecs;
+        self
+    }
+
+    /// Maps Confluent Schema Registry ids to registered types.
+    pub const fn with_schema_ids(mut self, schema_ids: &'static [(u32, TypeKey)]) -> Self {
+        self.schema_ids = schema_ids;
+        self
+    }
+
+    /// Short aliases used by compact envelopes in place of the 16-byte type_id.
+    pub const fn with_type_aliases(mut self, type_aliases: &'static [(u32, Uuid)]) -> Self {
+        self.type_aliases = type_aliases;
+        self
+    }
+
+    /// Decoders for unregistered types, matched by codec id (see `SchemalessDecoderEntry`).
+    pub const fn with_fallback_decoders(
+        mut self,
+        fallback_decoders: &'static [&'static dyn DecoderEntry],
+    ) -> Self {
+        self.fallback_decoders = fallback_decoders;
+        self
+    }
+
+    pub const fn with_key_provider(mut self, key_provider: &'static dyn KeyProvider) -> Self {
+        self.key_provider = Some(key_provider);
+        self
+    }
+
+    pub const fn with_dictionary_provider(
+        mut self,
+        dictionary_provider: &'static dyn DictionaryProvider,
+    ) -> Self {
+        self.dictionary_provider = Some(dictionary_provider);
+        self
     }
 }
 
@@ -95,4 +283,44 @@ impl Registry for StaticRegistry {
             .copied()
             .find(|action| action.id() == id)
     }
+
+    fn lookup_codec(&self, id: u16) -> Option<&'static dyn DynCodec> {
+        self.codecs.iter().copied().find(|codec| codec.id() == id)
+    }
+
+    fn lookup_schema_id(&self, schema_id: u32) -> Option<TypeKey> {
+        self.schema_ids
+            .iter()
+            .find(|(id, _)| *id == schema_id)
+            .map(|(_, key)| *key)
+    }
+
+    fn lookup_type_alias(&self, alias: u32) -> Option<Uuid> {
+        self.type_aliases
+            .iter()
+            .find(|(id, _)| *id == alias)
+            .map(|(_, type_id)| *type_id)
+    }
+
+    fn type_alias_for(&self, type_id: Uuid) -> Option<u32> {
+        self.type_aliases
+            .iter()
+            .find(|(_, id)| *id == type_id)
+            .map(|(alias, _)| *alias)
+    }
+
+    fn lookup_fallback_decoder(&self, codec_id: u16) -> Option<&'static dyn DecoderEntry> {
+        self.fallback_decoders
+            .iter()
+            .copied()
+            .find(|entry| entry.codec_id() == codec_id)
+    }
+
+    fn key_provider(&self) -> Option<&'static dyn KeyProvider> {
+        self.key_provider
+    }
+
+    fn dictionary_provider(&self) -> Option<&'static dyn DictionaryProvider> {
+        self.dictionary_provider
+    }
 }
@@ -0,0 +1,115 @@
+use crate::action::ActionSpecRef;
+use crate::codec::{CborCodec, CborValue, Codec, RmpCodec, DEFAULT_CBOR_MAX_DEPTH};
+use crate::error::DecodeError;
+use crate::registry::DecoderEntry;
+use crate::types::{DecodeLimits, TypeKey};
+use uuid::Uuid;
+
+/// Self-describing payload formats that decode without a Rust type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemalessFormat {
+    MessagePack,
+    Cbor,
+    Json,
+}
+
+/// Decoder entry that turns MessagePack, CBOR or JSON payloads into JSON as-is.
+///
+/// Binary strings and non-string map keys follow the [`CborValue`] mapping.
+#[derive(Debug, Clone, Copy)]
+pub struct SchemalessDecoderEntry {
+    key: TypeKey,
+    codec_id: u16,
+    format: SchemalessFormat,
+    byte_limit: u64,
+    max_depth: usize,
+    default_actions: &'static [ActionSpecRef],
+}
+
+impl SchemalessDecoderEntry {
+    pub const fn new(
+        key: TypeKey,
+        codec_id: u16,
+        format: SchemalessFormat,
+        default_actions: &'static [ActionSpecRef],
+    ) -> Self {
+        Self {
+            key,
+            codec_id,
+            format,
+            byte_limit: u64::MAX,
+            max_depth: DEFAULT_CBOR_MAX_DEPTH,
+            default_actions,
+        }
+    }
+
+    /// Entry for [`StaticRegistry::with_fallback_decoders`](crate::StaticRegistry::with_fallback_decoders),
+    /// used for any type without a registered decoder.
+    pub const fn fallback(codec_id: u16, format: SchemalessFormat) -> Self {
+        let key = TypeKey {
+            type_id: Uuid::nil(),
+            schema_version: 0,
+        };
+        Self::new(key, codec_id, format, &[])
+    }
+
+    pub const fn with_byte_limit(mut self, byte_limit: u64) -> Self {
+        self.byte_limit = byte_limit;
+        self
+    }
+
+    /// Nesting limit for CBOR payloads.
+    pub const fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub const fn format(&self) -> SchemalessFormat {
+        self.format
+    }
+}
+
+impl DecoderEntry for SchemalessDecoderEntry {
+    fn key(&self) -> TypeKey {
+        self.key
+    }
+
+    fn codec_id(&self) -> u16 {
+        self.codec_id
+    }
+
+    fn default_actions(&self) -> &'static [ActionSpecRef] {
+        self.default_actions
+    }
+
+    fn decode_payload(
+        &self,
+        payload: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<serde_json::Value, DecodeError> {
+        match self.format {
+            SchemalessFormat::MessagePack => {
+                let codec = RmpCodec::new(self.codec_id, self.byte_limit);
+                let value: CborValue = codec.decode(payload, limits)?;
+                Ok(value.to_json())
+            }
+            SchemalessFormat::Cbor => {
+                let codec =
+                    CborCodec::new(self.codec_id, self.byte_limit).with_max_depth(self.max_depth);
+                let value: CborValue = codec.decode(payload, limits)?;
+                Ok(value.to_json())
+            }
+            SchemalessFormat::Json => {
+                let limit = self.byte_limit.min(limits.max_output_bytes as u64);
+                if payload.len() as u64 > limit {
+                    return Err(DecodeError::LimitExceeded {
+                        context: "codec_input_bytes",
+                        limit: limit as usize,
+                        actual: payload.len(),
+                    });
+                }
+                Ok(serde_json::from_slice(payload)?)
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::action::{ensure_encoded_len, ActionContext, ByteAction};
+use crate::error::DecodeError;
+use crate::types::{DecodeLimits, EncodeLimits};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const HMAC_SHA256_TAG_LEN: usize = 32;
+pub const ED25519_SIGNATURE_LEN: usize = 64;
+/// Envelope fields covered by the tag ahead of the action input.
+pub const ENVELOPE_BINDING_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// Shared secret of any length.
+    HmacSha256,
+    /// Verifies with a 32-byte public key; signing needs the 64-byte keypair
+    /// (secret seed followed by public key).
+    Ed25519,
+}
+
+/// Checks and strips a trailing tag on decode, appends it on encode.
+///
+/// Params are the little-endian `u32` id of a key from the registry's key provider. The id
+/// comes from the envelope, so set [`with_key_ids`](Self::with_key_ids) whenever the provider
+/// also holds keys that must not verify signatures (encryption keys, Ed25519 public keys).
+/// The tag covers the envelope's type_id, schema_version (LE) and codec_id (LE) followed by
+/// the action input, so a signed payload cannot be relabelled as another type or codec.
+/// Pair it with [`TypedDecoderEntry::with_required_actions`](crate::TypedDecoderEntry::with_required_actions)
+/// so envelopes that simply leave the action out are rejected too.
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureAction {
+    pub id: u16,
+    pub algorithm: SignatureAlgorithm,
+    /// Key ids allowed to sign; `None` accepts any key the provider has.
+    pub key_ids: Option<&'static [u32]>,
+}
+
+impl SignatureAction {
+    pub const fn new(id: u16, algorithm: SignatureAlgorithm) -> Self {
+        Self {
+            id,
+            algorithm,
+            key_ids: None,
+        }
+    }
+
+    pub const fn with_key_ids(mut self, key_ids: &'static [u32]) -> Self {
+        self.key_ids = Some(key_ids);
+        self
+    }
+
+    fn allows(&self, key_id: u32) -> bool {
+        self.key_ids.is_none_or(|key_ids| key_ids.contains(&key_id))
+    }
+
+    fn signing_key_id(&self, params: &[u8]) -> Result<u32, DecodeError> {
+        let key_id = self.key_id(params)?;
+        if !self.allows(key_id) {
+            return Err(DecodeError::InvalidKey(format!(
+                "key {key_id} is not allowed for action {}",
+                self.id
+            )));
+        }
+        Ok(key_id)
+    }
+
+    pub const fn tag_len(&self) -> usize {
+        match self.algorithm {
+            SignatureAlgorithm::HmacSha256 => HMAC_SHA256_TAG_LEN,
+            SignatureAlgorithm::Ed25519 => ED25519_SIGNATURE_LEN,
+        }
+    }
+
+    fn key_id(&self, params: &[u8]) -> Result<u32, DecodeError> {
+        let key_id: [u8; 4] = params
+            .try_into()
+            .map_err(|_| DecodeError::InvalidActionParams {
+                id: self.id,
+                reason: "expected a 4-byte key id".to_string(),
+            })?;
+        Ok(u32::from_le_bytes(key_id))
+    }
+
+    fn no_context(&self) -> DecodeError {
+        DecodeError::InvalidKey(format!("action {} needs an action context", self.id))
+    }
+
+    fn envelope_binding(
+        &self,
+        context: &ActionContext<'_>,
+    ) -> Result<[u8; ENVELOPE_BINDING_LEN], DecodeError> {
+        let (Some(key), Some(codec_id)) = (context.associated_data(), context.codec_id) else {
+            return Err(DecodeError::InvalidKey(format!(
+                "action {} needs the envelope type key and codec id",
+                self.id
+            )));
+        };
+        let mut binding = [0u8; ENVELOPE_BINDING_LEN];
+        binding[..key.len()].copy_from_slice(&key);
+        binding[key.len()..].copy_from_slice(&codec_id.to_le_bytes());
+        Ok(binding)
+    }
+}
+
+impl ByteAction for SignatureAction {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn decode(
+        &self,
+        _input: &[u8],
+        _limits: &DecodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        Err(self.no_context())
+    }
+
+    fn encode(
+        &self,
+        _input: &[u8],
+        _limits: &EncodeLimits,
+        _params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        Err(self.no_context())
+    }
+
+    fn encode_params(&self, _input: &[u8], params: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        self.signing_key_id(params)?;
+        Ok(params.to_vec())
+    }
+
+    fn decode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &DecodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let key_id = self.key_id(params)?;
+        if !self.allows(key_id) {
+            return Err(DecodeError::SignatureInvalid { key_id });
+        }
+        let message_len = input
+            .len()
+            .checked_sub(self.tag_len())
+            .ok_or(DecodeError::SignatureInvalid { key_id })?;
+        if message_len > limits.max_output_bytes {
+            return Err(DecodeError::LimitExceeded {
+                context: "action_output_bytes",
+                limit: limits.max_output_bytes,
+                actual: message_len,
+            });
+        }
+        let (message, tag) = input.split_at(message_len);
+        let binding = self.envelope_binding(context)?;
+        let key = context.key_material(key_id)?;
+        let valid = match self.algorithm {
+            SignatureAlgorithm::HmacSha256 => hmac_sha256(key_id, &key, &binding, message)?
+                .verify_slice(tag)
+                .is_ok(),
+            SignatureAlgorithm::Ed25519 => {
+                let signature = Signature::from_slice(tag)
+                    .map_err(|_| DecodeError::SignatureInvalid { key_id })?;
+                ed25519_verifying_key(key_id, &key)?
+                    .verify_strict(&[&binding[..], message].concat(), &signature)
+                    .is_ok()
+            }
+        };
+        if !valid {
+            return Err(DecodeError::SignatureInvalid { key_id });
+        }
+        Ok(message.to_vec())
+    }
+
+    fn encode_in(
+        &self,
+        context: &ActionContext<'_>,
+        input: &[u8],
+        limits: &EncodeLimits,
+        params: &[u8],
+    ) -> Result<Vec<u8>, DecodeError> {
+        let key_id = self.signing_key_id(params)?;
+        let binding = self.envelope_binding(context)?;
+        let key = context.key_material(key_id)?;
+        let mut output = Vec::with_capacity(input.len() + self.tag_len());
+        output.extend_from_slice(input);
+        match self.algorithm {
+            SignatureAlgorithm::HmacSha256 => output.extend(
+                hmac_sha256(key_id, &key, &binding, input)?
+                    .finalize()
+                    .into_bytes(),
+            ),
+            SignatureAlgorithm::Ed25519 => {
+                let keypair: &[u8; 64] = key.as_slice().try_into().map_err(|_| {
+                    DecodeError::InvalidKey(format!(
+                        "signing with key {key_id} needs the 64-byte Ed25519 keypair"
+                    ))
+                })?;
+                let signing_key = SigningKey::from_keypair_bytes(keypair).map_err(|_| {
+                    DecodeError::InvalidKey(format!("key {key_id} is not an Ed25519 keypair"))
+                })?;
+                let signature = signing_key.sign(&[&binding[..], input].concat());
+                output.extend_from_slice(&signature.to_bytes());
+            }
+        }
+        ensure_encoded_len(&output, limits)?;
+        Ok(output)
+    }
+}
+
+fn hmac_sha256(
+    key_id: u32,
+    key: &[u8],
+    binding: &[u8],
+    message: &[u8],
+) -> Result<Hmac<Sha256>, DecodeError> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .map_err(|_| DecodeError::InvalidKey(format!("key {key_id} is not a valid HMAC key")))?;
+    mac.update(binding);
+    mac.update(message);
+    Ok(mac)
+}
+
+/// Accepts the public key alone or the full keypair.
+fn ed25519_verifying_key(key_id: u32, key: &[u8]) -> Result<VerifyingKey, DecodeError> {
+    let invalid = || {
+        DecodeError::InvalidKey(format!(
+            "key {key_id} is not an Ed25519 public key or keypair"
+        ))
+    };
+    if let Ok(public) = <&[u8; 32]>::try_from(key) {
+        VerifyingKey::from_bytes(public).map_err(|_| invalid())
+    } else if let Ok(keypair) = <&[u8; 64]>::try_from(key) {
+        SigningKey::from_keypair_bytes(keypair)
+            .map(|signing_key| signing_key.verifying_key())
+            .map_err(|_| invalid())
+    } else {
+        Err(invalid())
+    }
+}
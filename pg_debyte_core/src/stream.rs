@@ -0,0 +1,131 @@
+use crate::error::DecodeError;
+use std::io::{ErrorKind, Read, Write};
+
+const FRAME_HEADER_LEN: usize = 4;
+
+/// Appends `envelope` to `output` with a little-endian u32 length prefix.
+pub fn append_frame(output: &mut Vec<u8>, envelope: &[u8]) -> Result<(), DecodeError> {
+    let len = frame_len(envelope)?;
+    output.reserve(FRAME_HEADER_LEN + envelope.len());
+    output.extend_from_slice(&len.to_le_bytes());
+    output.extend_from_slice(envelope);
+    Ok(())
+}
+
+pub fn write_frame<W: Write>(writer: &mut W, envelope: &[u8]) -> Result<usize, DecodeError> {
+    let len = frame_len(envelope)?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(envelope)?;
+    Ok(FRAME_HEADER_LEN + envelope.len())
+}
+
+fn frame_len(envelope: &[u8]) -> Result<u32, DecodeError> {
+    u32::try_from(envelope.len()).map_err(|_| DecodeError::LimitExceeded {
+        context: "frame_bytes",
+        limit: u32::MAX as usize,
+        actual: envelope.len(),
+    })
+}
+
+/// Iterates over length-prefixed envelopes stored back to back in `input`.
+pub fn frames(input: &[u8]) -> FrameIter<'_> {
+    FrameIter { input }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameIter<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Iterator for FrameIter<'a> {
+    type Item = Result<&'a [u8], DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+        if self.input.len() < FRAME_HEADER_LEN {
+            self.input = &[];
+            return Some(Err(DecodeError::BadEnvelope("frame header out of bounds")));
+        }
+        let len = u32::from_le_bytes([self.input[0], self.input[1], self.input[2], self.input[3]])
+            as usize;
+        let rest = &self.input[FRAME_HEADER_LEN..];
+        if rest.len() < len {
+            self.input = &[];
+            return Some(Err(DecodeError::BadEnvelope("frame out of bounds")));
+        }
+        let (frame, rest) = rest.split_at(len);
+        self.input = rest;
+        Some(Ok(frame))
+    }
+}
+
+/// Reads length-prefixed envelopes from `reader`, rejecting frames above `max_frame_bytes`.
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    reader: R,
+    max_frame_bytes: usize,
+    done: bool,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R, max_frame_bytes: usize) -> Self {
+        Self {
+            reader,
+            max_frame_bytes,
+            done: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>, DecodeError> {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        let mut filled = 0;
+        while filled < FRAME_HEADER_LEN {
+            match self.reader.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(DecodeError::BadEnvelope("frame header out of bounds")),
+                Ok(read) => filled += read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let len = u32::from_le_bytes(header) as usize;
+        if len > self.max_frame_bytes {
+            return Err(DecodeError::LimitExceeded {
+                context: "frame_bytes",
+                limit: self.max_frame_bytes,
+                actual: len,
+            });
+        }
+        let mut frame = vec![0u8; len];
+        self.reader.read_exact(&mut frame).map_err(|err| {
+            if err.kind() == ErrorKind::UnexpectedEof {
+                DecodeError::BadEnvelope("frame out of bounds")
+            } else {
+                err.into()
+            }
+        })?;
+        Ok(Some(frame))
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = Result<Vec<u8>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_frame().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
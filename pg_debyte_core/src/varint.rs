@@ -0,0 +1,44 @@
+use crate::error::DecodeError;
+
+const MAX_VARINT_LEN: usize = 10;
+
+pub(crate) fn write(mut value: u64, output: &mut Vec<u8>) {
+    while value >= 0x80 {
+        output.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+pub(crate) fn read(input: &[u8]) -> Result<(u64, usize), DecodeError> {
+    let mut value = 0u64;
+    for (index, byte) in input.iter().take(MAX_VARINT_LEN).enumerate() {
+        let bits = u64::from(byte & 0x7f);
+        if index == MAX_VARINT_LEN - 1 && bits > 1 {
+            return Err(DecodeError::BadEnvelope("varint overflow"));
+        }
+        value |= bits << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    if input.len() >= MAX_VARINT_LEN {
+        Err(DecodeError::BadEnvelope("varint overflow"))
+    } else {
+        Err(DecodeError::BadEnvelope("varint out of bounds"))
+    }
+}
+
+pub(crate) fn read_u32(input: &[u8]) -> Result<(u32, usize), DecodeError> {
+    let (value, len) = read(input)?;
+    let value =
+        u32::try_from(value).map_err(|_| DecodeError::BadEnvelope("varint out of range"))?;
+    Ok((value, len))
+}
+
+pub(crate) fn read_u16(input: &[u8]) -> Result<(u16, usize), DecodeError> {
+    let (value, len) = read(input)?;
+    let value =
+        u16::try_from(value).map_err(|_| DecodeError::BadEnvelope("varint out of range"))?;
+    Ok((value, len))
+}
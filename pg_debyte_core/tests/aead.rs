@@ -0,0 +1,372 @@
+#![cfg(feature = "aead")]
+
+use pg_debyte_core::action::{decode_actions, decode_actions_in, ActionContext, ActionSpec};
+use pg_debyte_core::aead::{
+    AeadAction, AeadAlgorithm, EnvelopeEncryptionAction, KEY_ID_LEN, NONCE_LEN, WRAPPED_KEY_LEN,
+};
+use pg_debyte_core::codec::{BincodeCodec, Codec};
+use pg_debyte_core::compact::try_parse_compact;
+use pg_debyte_core::encode::{
+    encode_to_compact_envelope, encode_to_envelope, rewrap_envelope, rotate_envelope_key,
+};
+use pg_debyte_core::envelope::{try_parse, try_parse_unverified, EnvelopeView, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::keys::{
+    parse_keys, CallbackKeyProvider, KeyBytes, KeyProvider, StaticKeyProvider,
+};
+use pg_debyte_core::registry::StaticRegistry;
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Secret {
+    id: u32,
+    email: String,
+}
+
+static AES: AeadAction = AeadAction::new(20, AeadAlgorithm::Aes256Gcm);
+static CHACHA: AeadAction = AeadAction::new(21, AeadAlgorithm::ChaCha20Poly1305);
+static KEYS: StaticKeyProvider =
+    StaticKeyProvider::new(&[(1, &[0x42; 32]), (2, &[0x17; 32]), (3, &[0x01; 16])]);
+static WRAPPED_AES: EnvelopeEncryptionAction =
+    EnvelopeEncryptionAction::new(22, AeadAlgorithm::Aes256Gcm);
+static WRAPPED_CHACHA: EnvelopeEncryptionAction =
+    EnvelopeEncryptionAction::new(23, AeadAlgorithm::ChaCha20Poly1305);
+static REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &[&AES, &CHACHA, &WRAPPED_AES, &WRAPPED_CHACHA])
+        .with_key_provider(&KEYS)
+        .with_type_aliases(&[(9, Uuid::from_bytes([0x5e; 16]))]);
+/// Only the rotated-to master key, to show the old one is no longer needed.
+static ROTATED_KEYS: StaticKeyProvider = StaticKeyProvider::new(&[(2, &[0x17; 32])]);
+static ROTATED_REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &[&WRAPPED_AES, &WRAPPED_CHACHA]).with_key_provider(&ROTATED_KEYS);
+
+fn secret() -> Secret {
+    Secret {
+        id: 7,
+        email: "someone@example.com".to_string(),
+    }
+}
+
+fn key() -> TypeKey {
+    TypeKey {
+        type_id: Uuid::from_bytes([0x5e; 16]),
+        schema_version: 2,
+    }
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(4096, 4096, 4096)
+}
+
+fn encrypt(action_id: u16, key_id: u32) -> Vec<u8> {
+    let actions = [ActionSpec::new(action_id, 0, key_id.to_le_bytes().to_vec())];
+    encode_to_envelope(
+        &secret(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &actions,
+        &REGISTRY,
+        &EncodeLimits::new(4096),
+    )
+    .expect("encode")
+}
+
+fn view(envelope: &[u8]) -> EnvelopeView<'_> {
+    match try_parse(envelope).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    }
+}
+
+fn decrypt(view: &EnvelopeView<'_>, key: TypeKey) -> Result<Secret, DecodeError> {
+    decrypt_with(&REGISTRY, view, key)
+}
+
+fn decrypt_with(
+    registry: &StaticRegistry,
+    view: &EnvelopeView<'_>,
+    key: TypeKey,
+) -> Result<Secret, DecodeError> {
+    let context = ActionContext::new(registry).with_key(key);
+    let payload = decode_actions_in(&context, view.actions, view.payload, &limits())?;
+    BincodeCodec::new(1, 4096).decode(&payload, &limits())
+}
+
+#[test]
+fn aead_roundtrip_records_key_id_and_nonce() {
+    for action_id in [20, 21] {
+        let first = encrypt(action_id, 1);
+        let second = encrypt(action_id, 1);
+        let (first, second) = (view(&first), view(&second));
+
+        let params = first.actions.get(0).expect("action").params;
+        assert_eq!(params.len(), KEY_ID_LEN + NONCE_LEN);
+        assert_eq!(&params[..KEY_ID_LEN], &1u32.to_le_bytes());
+        assert_ne!(params, second.actions.get(0).expect("action").params);
+        assert_ne!(first.payload, second.payload);
+
+        assert_eq!(decrypt(&first, key()).expect("decrypt"), secret());
+        assert_eq!(decrypt(&second, key()).expect("decrypt"), secret());
+    }
+}
+
+#[test]
+fn aead_binds_type_key() {
+    let envelope = encrypt(20, 1);
+    let other = TypeKey {
+        schema_version: 3,
+        ..key()
+    };
+    match decrypt(&view(&envelope), other) {
+        Err(DecodeError::Aead(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    match decode_actions(
+        &REGISTRY,
+        view(&envelope).actions,
+        view(&envelope).payload,
+        &limits(),
+    ) {
+        Err(DecodeError::Aead(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn aead_rejects_tampering_and_wrong_keys() {
+    let mut envelope = encrypt(21, 1);
+    // Flip a tag bit and skip the checksum so the AEAD check is what fails.
+    let last = envelope.len() - 1;
+    envelope[last] ^= 0x01;
+    let tampered = match try_parse_unverified(&envelope).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+    match decrypt(&tampered, key()) {
+        Err(DecodeError::Aead(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    match encode_to_envelope(
+        &secret(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &[ActionSpec::new(20, 0, 9u32.to_le_bytes().to_vec())],
+        &REGISTRY,
+        &EncodeLimits::new(4096),
+    ) {
+        Err(DecodeError::UnknownKey(9)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match encode_to_envelope(
+        &secret(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &[ActionSpec::new(20, 0, 3u32.to_le_bytes().to_vec())],
+        &REGISTRY,
+        &EncodeLimits::new(4096),
+    ) {
+        Err(DecodeError::InvalidKey(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+    match encode_to_envelope(
+        &secret(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &[ActionSpec::new(20, 0, vec![1])],
+        &REGISTRY,
+        &EncodeLimits::new(4096),
+    ) {
+        Err(DecodeError::InvalidActionParams { id: 20, .. }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn aead_decode_checks_output_limit() {
+    let envelope = encrypt(20, 1);
+    let view = view(&envelope);
+    let context = ActionContext::new(&REGISTRY).with_key(key());
+    let err = decode_actions_in(
+        &context,
+        view.actions,
+        view.payload,
+        &DecodeLimits::new(4096, 8, 4096),
+    )
+    .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "action_output_bytes"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn rewrap_encrypts_plain_envelope() {
+    let plain = encode_to_envelope(
+        &secret(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &[],
+        &REGISTRY,
+        &EncodeLimits::new(4096),
+    )
+    .expect("encode");
+    let actions = [ActionSpec::new(21, 0, 2u32.to_le_bytes().to_vec())];
+    let encrypted = rewrap_envelope(
+        &plain,
+        &actions,
+        &REGISTRY,
+        &limits(),
+        &EncodeLimits::new(4096),
+    )
+    .expect("rewrap");
+    assert_eq!(
+        decrypt(&view(&encrypted), key()).expect("decrypt"),
+        secret()
+    );
+}
+
+#[test]
+fn callback_provider_is_set_once() {
+    let provider = CallbackKeyProvider::new();
+    assert!(provider.key(1).expect("lookup").is_none());
+    provider.set(|key_id| (key_id == 1).then(|| KeyBytes::new(vec![0xaa; 32])));
+    provider.set(|_| None);
+    assert_eq!(
+        provider.key(1).expect("lookup").expect("key").as_slice(),
+        &[0xaa; 32]
+    );
+    assert!(provider.key(2).expect("lookup").is_none());
+}
+
+#[test]
+fn parse_keys_reads_lists_and_files() {
+    let keys = parse_keys("1:00ff, 2:0102\n# retired\n\n3:ab # current\n").expect("parse");
+    let keys: Vec<(u32, Vec<u8>)> = keys
+        .into_iter()
+        .map(|(id, key)| (id, key.to_vec()))
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            (1, vec![0x00, 0xff]),
+            (2, vec![0x01, 0x02]),
+            (3, vec![0xab])
+        ]
+    );
+
+    for bad in ["1", "x:00", "1:zz"] {
+        match parse_keys(bad) {
+            Err(DecodeError::InvalidKey(message)) => assert!(!message.contains("zz")),
+            other => panic!("unexpected result for {bad:?}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn envelope_encryption_wraps_a_data_key_per_payload() {
+    for action_id in [22, 23] {
+        let first = encrypt(action_id, 1);
+        let second = encrypt(action_id, 1);
+        let (first, second) = (view(&first), view(&second));
+
+        let params = first.actions.get(0).expect("action").params;
+        assert_eq!(params.len(), KEY_ID_LEN + WRAPPED_KEY_LEN + NONCE_LEN);
+        assert_eq!(&params[..KEY_ID_LEN], &1u32.to_le_bytes());
+        let other = second.actions.get(0).expect("action").params;
+        assert_ne!(
+            params[KEY_ID_LEN..KEY_ID_LEN + WRAPPED_KEY_LEN],
+            other[KEY_ID_LEN..KEY_ID_LEN + WRAPPED_KEY_LEN]
+        );
+
+        assert_eq!(decrypt(&first, key()).expect("decrypt"), secret());
+        assert_eq!(decrypt(&second, key()).expect("decrypt"), secret());
+    }
+}
+
+#[test]
+fn rotate_key_rewraps_only_the_data_key() {
+    for action_id in [22, 23] {
+        let envelope = encrypt(action_id, 1);
+        let rotated = rotate_envelope_key(&envelope, 2, &REGISTRY).expect("rotate");
+        let (before, after) = (view(&envelope), view(&rotated));
+
+        assert_eq!(after.payload, before.payload);
+        assert_eq!(after.key, before.key);
+        let (old_params, new_params) = (
+            before.actions.get(0).expect("action").params,
+            after.actions.get(0).expect("action").params,
+        );
+        assert_eq!(&new_params[..KEY_ID_LEN], &2u32.to_le_bytes());
+        assert_eq!(
+            old_params[KEY_ID_LEN + WRAPPED_KEY_LEN..],
+            new_params[KEY_ID_LEN + WRAPPED_KEY_LEN..]
+        );
+
+        assert_eq!(
+            decrypt_with(&ROTATED_REGISTRY, &after, key()).expect("decrypt"),
+            secret()
+        );
+        match decrypt_with(&ROTATED_REGISTRY, &before, key()) {
+            Err(DecodeError::UnknownKey(1)) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn rotate_key_handles_compact_envelopes_and_stacked_actions() {
+    let envelope = encode_to_compact_envelope(
+        &secret(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &[
+            ActionSpec::new(22, 0, 1u32.to_le_bytes().to_vec()),
+            ActionSpec::new(20, 0, 1u32.to_le_bytes().to_vec()),
+        ],
+        &REGISTRY,
+        &EncodeLimits::new(4096),
+    )
+    .expect("encode");
+    let rotated = rotate_envelope_key(&envelope, 2, &REGISTRY).expect("rotate");
+
+    let view = try_parse_compact(&rotated)
+        .expect("parse")
+        .expect("compact envelope");
+    assert_eq!(view.alias, 9);
+    assert_eq!(
+        &view.actions.get(0).expect("action").params[..4],
+        &2u32.to_le_bytes()
+    );
+    // The static-key action has no data key and keeps its params.
+    assert_eq!(
+        &view.actions.get(1).expect("action").params[..4],
+        &1u32.to_le_bytes()
+    );
+
+    let context = ActionContext::new(&REGISTRY).with_key(key());
+    let payload =
+        decode_actions_in(&context, view.actions, view.payload, &limits()).expect("decode");
+    let decoded: Secret = BincodeCodec::new(1, 4096)
+        .decode(&payload, &limits())
+        .expect("decode");
+    assert_eq!(decoded, secret());
+}
+
+#[test]
+fn rotate_key_rejects_envelopes_without_wrapped_keys() {
+    let envelope = encrypt(20, 1);
+    match rotate_envelope_key(&envelope, 2, &REGISTRY) {
+        Err(DecodeError::BadEnvelope(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let envelope = encrypt(22, 1);
+    match rotate_envelope_key(&envelope, 9, &REGISTRY) {
+        Err(DecodeError::UnknownKey(9)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
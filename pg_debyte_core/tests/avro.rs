@@ -0,0 +1,395 @@
+#![cfg(feature = "avro")]
+
+use apache_avro::types::Value as ReferenceValue;
+use pg_debyte_core::avro::{decode_avro, AvroDecoderEntry, AvroSchema};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::DecoderEntry;
+use pg_debyte_core::types::{DecodeLimits, TypeKey};
+use serde_json::json;
+use uuid::Uuid;
+
+const KEY: TypeKey = TypeKey {
+    type_id: Uuid::from_bytes([0x77; 16]),
+    schema_version: 1,
+};
+const AVRO_CODEC_ID: u16 = 5;
+
+const USER_V1: &str = r#"{
+    "type": "record",
+    "name": "User",
+    "namespace": "demo",
+    "fields": [
+        {"name": "id", "type": "int"},
+        {"name": "name", "type": "string"},
+        {"name": "legacy", "type": "string"},
+        {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B", "C"]}}
+    ]
+}"#;
+
+const USER_V2: &str = r#"{
+    "type": "record",
+    "name": "User",
+    "namespace": "demo",
+    "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "full_name", "type": "string", "aliases": ["name"]},
+        {"name": "email", "type": ["null", "string"], "default": null},
+        {"name": "status", "type": "string", "default": "active"},
+        {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B", "OTHER"], "default": "OTHER"}}
+    ]
+}"#;
+
+fn long(value: i64, output: &mut Vec<u8>) {
+    let mut zigzag = ((value << 1) ^ (value >> 63)) as u64;
+    loop {
+        let byte = (zigzag & 0x7f) as u8;
+        zigzag >>= 7;
+        if zigzag == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn string(value: &str, output: &mut Vec<u8>) {
+    long(value.len() as i64, output);
+    output.extend_from_slice(value.as_bytes());
+}
+
+fn user_v1(id: i64, name: &str, kind: i64) -> Vec<u8> {
+    let mut output = Vec::new();
+    long(id, &mut output);
+    string(name, &mut output);
+    string("dropped", &mut output);
+    long(kind, &mut output);
+    output
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(1024, 1024, 1024)
+}
+
+#[test]
+fn avro_decodes_with_writer_schema() {
+    let entry = AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, USER_V1, &[]);
+    let value = entry
+        .decode_payload(&user_v1(7, "ada", 1), &limits())
+        .expect("decode");
+
+    assert_eq!(entry.codec_id(), AVRO_CODEC_ID);
+    assert_eq!(
+        value,
+        json!({"id": 7, "name": "ada", "legacy": "dropped", "kind": "B"})
+    );
+}
+
+#[test]
+fn avro_resolves_writer_against_reader_schema() {
+    static ENTRY: AvroDecoderEntry =
+        AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, USER_V1, &[]).with_reader_schema(USER_V2);
+    let value = ENTRY
+        .decode_payload(&user_v1(-3, "ada", 2), &limits())
+        .expect("decode");
+
+    assert_eq!(
+        value,
+        json!({
+            "id": -3,
+            "full_name": "ada",
+            "email": null,
+            "status": "active",
+            "kind": "OTHER",
+        })
+    );
+}
+
+#[test]
+fn avro_renders_bytes_defaults_like_decoded_bytes() {
+    let reader = r#"{"type": "record", "name": "User", "namespace": "demo", "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "token", "type": "bytes", "default": "\u00ff\u0001"},
+        {"name": "tag", "type": {"type": "fixed", "name": "Tag", "size": 2}, "default": "ab"},
+        {"name": "history", "type": {"type": "array", "items": "bytes"}, "default": ["\u0000"]},
+        {"name": "salt", "type": ["bytes", "null"], "default": ""}
+    ]}"#;
+    let entry = AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, USER_V1, &[]).with_reader_schema(reader);
+    let value = entry
+        .decode_payload(&user_v1(1, "ada", 0), &limits())
+        .expect("decode");
+
+    assert_eq!(
+        value,
+        json!({
+            "id": 1,
+            "token": "\\xff01",
+            "tag": "\\x6162",
+            "history": ["\\x00"],
+            "salt": "\\x",
+        })
+    );
+
+    let invalid = r#"{"type": "record", "name": "User", "namespace": "demo", "fields": [
+        {"name": "token", "type": "bytes", "default": "\u0100"}
+    ]}"#;
+    let entry = AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, USER_V1, &[]).with_reader_schema(invalid);
+    assert!(matches!(
+        entry.decode_payload(&user_v1(1, "ada", 0), &limits()),
+        Err(DecodeError::Avro(_))
+    ));
+}
+
+#[test]
+fn avro_reports_missing_field_without_default() {
+    let reader = r#"{"type": "record", "name": "User", "namespace": "demo", "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "required", "type": "string"}
+    ]}"#;
+    let entry = AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, USER_V1, &[]).with_reader_schema(reader);
+    match entry.decode_payload(&user_v1(1, "ada", 0), &limits()) {
+        Err(DecodeError::Avro(message)) => assert!(message.contains("required")),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn avro_decodes_unions_collections_and_recursive_types() {
+    let schema = AvroSchema::parse(
+        r#"{"type": "record", "name": "Node", "fields": [
+            {"name": "value", "type": ["null", "double"]},
+            {"name": "raw", "type": {"type": "fixed", "name": "Pair", "size": 2}},
+            {"name": "labels", "type": {"type": "map", "values": "boolean"}},
+            {"name": "next", "type": ["null", "Node"]}
+        ]}"#,
+    )
+    .expect("schema");
+
+    let mut payload = Vec::new();
+    long(1, &mut payload);
+    payload.extend_from_slice(&1.5f64.to_le_bytes());
+    payload.extend_from_slice(&[0xbe, 0xef]);
+    long(-1, &mut payload);
+    long(4, &mut payload);
+    string("ok", &mut payload);
+    payload.push(1);
+    long(0, &mut payload);
+    long(1, &mut payload);
+    long(0, &mut payload);
+    payload.extend_from_slice(&[0x00, 0x01]);
+    long(0, &mut payload);
+    long(0, &mut payload);
+
+    let value = decode_avro(&payload, &schema, &schema, &limits()).expect("decode");
+    assert_eq!(
+        value,
+        json!({
+            "value": 1.5,
+            "raw": "\\xbeef",
+            "labels": {"ok": true},
+            "next": {"value": null, "raw": "\\x0001", "labels": {}, "next": null},
+        })
+    );
+}
+
+#[test]
+fn avro_rejects_unresolvable_types() {
+    let writer = AvroSchema::parse(r#""long""#).expect("writer");
+    let reader = AvroSchema::parse(r#""int""#).expect("reader");
+    let mut payload = Vec::new();
+    long(1, &mut payload);
+    assert!(matches!(
+        decode_avro(&payload, &writer, &reader, &limits()),
+        Err(DecodeError::Avro(_))
+    ));
+}
+
+#[test]
+fn avro_rejects_truncated_and_trailing_input() {
+    let entry = AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, USER_V1, &[]);
+    let payload = user_v1(1, "ada", 0);
+    assert!(matches!(
+        entry.decode_payload(&payload[..payload.len() - 1], &limits()),
+        Err(DecodeError::Avro(_))
+    ));
+
+    let mut trailing = payload.clone();
+    trailing.push(0);
+    assert!(matches!(
+        entry.decode_payload(&trailing, &limits()),
+        Err(DecodeError::Avro(_))
+    ));
+}
+
+#[test]
+fn avro_respects_limits() {
+    let entry = AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, USER_V1, &[]);
+    match entry.decode_payload(&user_v1(1, "ada", 0), &DecodeLimits::new(1024, 4, 1024)) {
+        Err(DecodeError::LimitExceeded { context, .. }) => {
+            assert_eq!(context, "codec_input_bytes")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let nulls = AvroSchema::parse(r#"{"type": "array", "items": "null"}"#).expect("schema");
+    let mut payload = Vec::new();
+    long(i64::MAX, &mut payload);
+    match decode_avro(&payload, &nulls, &nulls, &limits()) {
+        Err(DecodeError::LimitExceeded { context, limit, .. }) => {
+            assert_eq!(context, "codec_output_values");
+            assert_eq!(limit, 1024);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn avro_reports_invalid_schema() {
+    let entry = AvroDecoderEntry::new(KEY, AVRO_CODEC_ID, r#"{"type": "record"}"#, &[]);
+    match entry.decode_payload(&[], &limits()) {
+        Err(DecodeError::Avro(message)) => assert!(message.starts_with("invalid schema")),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+/// Encodes `value` with the reference implementation, then checks our resolution of
+/// `writer` against `reader` matches apache-avro's.
+fn cross_check(writer: &str, reader: &str, value: ReferenceValue) -> serde_json::Value {
+    let reference_writer = apache_avro::Schema::parse_str(writer).expect("reference writer");
+    let reference_reader = apache_avro::Schema::parse_str(reader).expect("reference reader");
+    let payload = apache_avro::to_avro_datum(&reference_writer, value).expect("reference encode");
+    let expected = apache_avro::from_avro_datum(
+        &reference_writer,
+        &mut payload.as_slice(),
+        Some(&reference_reader),
+    )
+    .expect("reference decode");
+    let expected = serde_json::Value::try_from(expected).expect("reference json");
+
+    let writer = AvroSchema::parse(writer).expect("writer");
+    let reader = AvroSchema::parse(reader).expect("reader");
+    let value = decode_avro(&payload, &writer, &reader, &limits()).expect("decode");
+    assert_eq!(value, expected);
+    value
+}
+
+fn user_record(fields: Vec<(&str, ReferenceValue)>) -> ReferenceValue {
+    ReferenceValue::Record(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+#[test]
+fn avro_matches_reference_resolution() {
+    let writer = r#"{"type": "record", "name": "User", "namespace": "demo", "fields": [
+        {"name": "id", "type": "int"},
+        {"name": "name", "type": "string"},
+        {"name": "legacy", "type": "string"},
+        {"name": "score", "type": "int"},
+        {"name": "ratio", "type": "float"}
+    ]}"#;
+    let reader = r#"{"type": "record", "name": "User", "namespace": "demo", "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "name", "type": "string"},
+        {"name": "score", "type": "double"},
+        {"name": "ratio", "type": "double"},
+        {"name": "status", "type": "string", "default": "active"},
+        {"name": "email", "type": ["null", "string"], "default": null}
+    ]}"#;
+    let value = cross_check(
+        writer,
+        reader,
+        user_record(vec![
+            ("id", ReferenceValue::Int(-7)),
+            ("name", ReferenceValue::String("ada".to_string())),
+            ("legacy", ReferenceValue::String("dropped".to_string())),
+            ("score", ReferenceValue::Int(12)),
+            ("ratio", ReferenceValue::Float(0.5)),
+        ]),
+    );
+
+    assert_eq!(
+        value,
+        json!({
+            "id": -7,
+            "name": "ada",
+            "score": 12.0,
+            "ratio": 0.5,
+            "status": "active",
+            "email": null,
+        })
+    );
+}
+
+#[test]
+fn avro_matches_reference_binary_encoding() {
+    let schema = r#"{"type": "record", "name": "Event", "namespace": "demo", "fields": [
+        {"name": "id", "type": "long"},
+        {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B"]}},
+        {"name": "tags", "type": {"type": "array", "items": "string"}},
+        {"name": "counts", "type": {"type": "map", "values": "int"}},
+        {"name": "note", "type": ["null", "string"]},
+        {"name": "ok", "type": "boolean"},
+        {"name": "weight", "type": "double"}
+    ]}"#;
+    let value = cross_check(
+        schema,
+        schema,
+        user_record(vec![
+            ("id", ReferenceValue::Long(1 << 40)),
+            ("kind", ReferenceValue::Enum(1, "B".to_string())),
+            (
+                "tags",
+                ReferenceValue::Array(vec![
+                    ReferenceValue::String("x".to_string()),
+                    ReferenceValue::String("y".to_string()),
+                ]),
+            ),
+            (
+                "counts",
+                ReferenceValue::Map(
+                    [("a".to_string(), ReferenceValue::Int(-300))]
+                        .into_iter()
+                        .collect(),
+                ),
+            ),
+            (
+                "note",
+                ReferenceValue::Union(1, Box::new(ReferenceValue::String("hi".to_string()))),
+            ),
+            ("ok", ReferenceValue::Boolean(true)),
+            ("weight", ReferenceValue::Double(-2.25)),
+        ]),
+    );
+
+    assert_eq!(value["counts"], json!({"a": -300}));
+}
+
+#[test]
+fn avro_named_types_resolve_by_full_name() {
+    let writer = r#"{"type": "record", "name": "User", "namespace": "b", "fields": [
+        {"name": "id", "type": "int"}
+    ]}"#;
+    let reader = r#"{"type": "record", "name": "User", "namespace": "a", "fields": [
+        {"name": "id", "type": "int"}
+    ]}"#;
+    let aliased = r#"{"type": "record", "name": "User", "namespace": "a", "aliases": ["b.User"],
+        "fields": [{"name": "id", "type": "int"}]}"#;
+    let mut payload = Vec::new();
+    long(5, &mut payload);
+    let writer = AvroSchema::parse(writer).expect("writer");
+
+    let reader = AvroSchema::parse(reader).expect("reader");
+    assert!(matches!(
+        decode_avro(&payload, &writer, &reader, &limits()),
+        Err(DecodeError::Avro(_))
+    ));
+
+    let aliased = AvroSchema::parse(aliased).expect("aliased");
+    assert_eq!(
+        decode_avro(&payload, &writer, &aliased, &limits()).expect("decode"),
+        json!({"id": 5})
+    );
+}
@@ -0,0 +1,174 @@
+use bincode::Options;
+use pg_debyte_core::codec::{BincodeCodec, BincodeOptions, Codec, Endian, IntEncoding};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::{DecoderEntry, TypedDecoderEntry};
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Demo {
+    id: u32,
+    name: String,
+}
+
+fn demo() -> Demo {
+    Demo {
+        id: 1,
+        name: "demo".to_string(),
+    }
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(1024, 1024, 1024)
+}
+
+const LEGACY_CODEC: BincodeCodec = BincodeCodec::new(1, 1024).with_options(BincodeOptions::LEGACY);
+
+#[test]
+fn legacy_options_read_bincode_serialize_output() {
+    let bytes = bincode::serialize(&demo()).expect("serialize");
+    let decoded: Demo = LEGACY_CODEC.decode(&bytes, &limits()).expect("decode");
+    assert_eq!(decoded, demo());
+    assert_eq!(
+        LEGACY_CODEC
+            .encode(&demo(), &EncodeLimits::new(1024))
+            .expect("encode"),
+        bytes
+    );
+
+    assert!(BincodeCodec::new(1, 1024)
+        .decode::<Demo>(&bytes, &limits())
+        .is_err());
+}
+
+#[test]
+fn default_options_match_bincode_default_options() {
+    let codec = BincodeCodec::new(1, 1024);
+    assert_eq!(codec.options, BincodeOptions::DEFAULT);
+    let expected = bincode::DefaultOptions::new()
+        .serialize(&demo())
+        .expect("serialize");
+    assert_eq!(
+        codec
+            .encode(&demo(), &EncodeLimits::new(1024))
+            .expect("encode"),
+        expected
+    );
+}
+
+#[test]
+fn big_endian_fixint_roundtrip() {
+    let codec = BincodeCodec::new(1, 1024).with_options(
+        BincodeOptions::DEFAULT
+            .with_int_encoding(IntEncoding::Fixint)
+            .with_endian(Endian::Big),
+    );
+    let bytes = codec
+        .encode(&demo(), &EncodeLimits::new(1024))
+        .expect("encode");
+    assert_eq!(&bytes[..4], &[0, 0, 0, 1]);
+    let decoded: Demo = codec.decode(&bytes, &limits()).expect("decode");
+    assert_eq!(decoded, demo());
+}
+
+#[test]
+fn trailing_bytes_follow_options() {
+    let mut bytes = bincode::DefaultOptions::new()
+        .serialize(&demo())
+        .expect("serialize");
+    bytes.push(0xff);
+
+    let strict = BincodeCodec::new(1, 1024);
+    assert!(matches!(
+        strict.decode::<Demo>(&bytes, &limits()),
+        Err(DecodeError::Bincode(_))
+    ));
+
+    let lenient = strict.with_options(BincodeOptions::DEFAULT.with_trailing_bytes(true));
+    let decoded: Demo = lenient.decode(&bytes, &limits()).expect("decode");
+    assert_eq!(decoded, demo());
+}
+
+#[test]
+fn options_work_in_static_decoder_entries() {
+    static ENTRY: TypedDecoderEntry<Demo, BincodeCodec> = TypedDecoderEntry::new(
+        TypeKey {
+            type_id: Uuid::from_bytes([0x88; 16]),
+            schema_version: 1,
+        },
+        LEGACY_CODEC,
+        &[],
+    );
+    let bytes = bincode::serialize(&demo()).expect("serialize");
+    let value = ENTRY.decode_payload(&bytes, &limits()).expect("decode");
+    assert_eq!(value, json!({"id": 1, "name": "demo"}));
+}
+
+#[cfg(feature = "bincode2")]
+mod bincode2 {
+    use super::*;
+    use pg_debyte_core::codec::Bincode2Codec;
+
+    #[test]
+    fn bincode2_reads_bincode1_encodings() {
+        let standard: Bincode2Codec = Bincode2Codec::new(6);
+        let bytes = bincode::DefaultOptions::new()
+            .serialize(&demo())
+            .expect("serialize");
+        let decoded: Demo = standard.decode(&bytes, &limits()).expect("decode");
+        assert_eq!(decoded, demo());
+
+        let legacy: Bincode2Codec = Bincode2Codec::new(6).with_options(BincodeOptions::LEGACY);
+        let bytes = bincode::serialize(&demo()).expect("serialize");
+        let decoded: Demo = legacy.decode(&bytes, &limits()).expect("decode");
+        assert_eq!(decoded, demo());
+    }
+
+    #[test]
+    fn bincode2_roundtrip_and_trailing_bytes() {
+        let codec: Bincode2Codec = Bincode2Codec::new(6);
+        let mut bytes = codec
+            .encode(&demo(), &EncodeLimits::new(1024))
+            .expect("encode");
+        let decoded: Demo = codec.decode(&bytes, &limits()).expect("decode");
+        assert_eq!(decoded, demo());
+
+        bytes.push(0);
+        assert!(matches!(
+            codec.decode::<Demo>(&bytes, &limits()),
+            Err(DecodeError::Bincode(_))
+        ));
+        let lenient = codec.with_options(BincodeOptions::DEFAULT.with_trailing_bytes(true));
+        assert!(lenient.decode::<Demo>(&bytes, &limits()).is_ok());
+    }
+
+    #[test]
+    fn bincode2_const_limit_bounds_length_prefixes() {
+        let codec: Bincode2Codec<64> = Bincode2Codec::new(6);
+        // id = 1, then a string claiming u32::MAX bytes.
+        let bytes = [0x01, 0xfc, 0xff, 0xff, 0xff, 0xff];
+        assert!(matches!(
+            codec.decode::<Demo>(&bytes, &limits()),
+            Err(DecodeError::Bincode(_))
+        ));
+    }
+
+    #[test]
+    fn bincode2_respects_runtime_limits() {
+        let codec: Bincode2Codec = Bincode2Codec::new(6);
+        match codec.encode(&demo(), &EncodeLimits::new(2)) {
+            Err(DecodeError::LimitExceeded { context, .. }) => {
+                assert_eq!(context, "codec_output_bytes")
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        match codec.decode::<Demo>(&[0u8; 16], &DecodeLimits::new(1024, 8, 1024)) {
+            Err(DecodeError::LimitExceeded { context, .. }) => {
+                assert_eq!(context, "codec_input_bytes")
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use ciborium::Value;
+use pg_debyte_core::codec::{CborCodec, CborValue, Codec};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::{DecoderEntry, TypedDecoderEntry};
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Reading {
+    sensor: String,
+    celsius: f32,
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(1024, 1024, 1024)
+}
+
+fn to_cbor(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes).expect("encode");
+    bytes
+}
+
+#[test]
+fn cbor_roundtrip() {
+    let codec = CborCodec::new(3, 1024);
+    let reading = Reading {
+        sensor: "t1".to_string(),
+        celsius: 21.5,
+    };
+    let bytes = codec
+        .encode(&reading, &EncodeLimits::new(1024))
+        .expect("encode");
+    let decoded: Reading = codec.decode(&bytes, &limits()).expect("decode");
+    assert_eq!(decoded, reading);
+}
+
+#[test]
+fn cbor_value_maps_bytes_tags_and_keys() {
+    let input = Value::Map(vec![
+        (Value::Integer(1.into()), Value::Text("one".into())),
+        (Value::Text("raw".into()), Value::Bytes(vec![0xde, 0xad])),
+        (
+            Value::Text("when".into()),
+            Value::Tag(1, Box::new(Value::Integer(1_700_000_000.into()))),
+        ),
+        (Value::Bool(true), Value::Null),
+        (
+            Value::Array(vec![Value::Integer(1.into())]),
+            Value::Float(1.5),
+        ),
+    ]);
+    let decoded: CborValue = CborCodec::new(3, 1024)
+        .decode(&to_cbor(&input), &limits())
+        .expect("decode");
+
+    assert_eq!(
+        decoded.to_json(),
+        json!({
+            "1": "one",
+            "raw": "\\xdead",
+            "when": {"tag": 1, "value": 1_700_000_000},
+            "true": null,
+            "[1]": 1.5,
+        })
+    );
+}
+
+#[test]
+fn cbor_decoder_entry_uses_value_mapping() {
+    static ENTRY: TypedDecoderEntry<CborValue, CborCodec> = TypedDecoderEntry::new(
+        TypeKey {
+            type_id: Uuid::from_bytes([0x55; 16]),
+            schema_version: 1,
+        },
+        CborCodec::new(3, 1024),
+        &[],
+    );
+    let input = Value::Array(vec![
+        Value::Bytes(vec![1, 2]),
+        Value::Integer(u64::MAX.into()),
+        Value::Integer((-1i64).into()),
+    ]);
+    let value = ENTRY
+        .decode_payload(&to_cbor(&input), &limits())
+        .expect("decode");
+    assert_eq!(value, json!(["\\x0102", u64::MAX, -1]));
+}
+
+#[test]
+fn cbor_decode_respects_input_limit() {
+    let bytes = to_cbor(&Value::Text("long enough".into()));
+    match CborCodec::new(3, 4).decode::<CborValue>(&bytes, &limits()) {
+        Err(DecodeError::LimitExceeded { context, .. }) => {
+            assert_eq!(context, "codec_input_bytes")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn cbor_decode_respects_depth_limit() {
+    let mut nested = Value::Null;
+    for _ in 0..16 {
+        nested = Value::Array(vec![nested]);
+    }
+    let bytes = to_cbor(&nested);
+
+    let codec = CborCodec::new(3, 1024);
+    assert!(codec.decode::<CborValue>(&bytes, &limits()).is_ok());
+    assert!(matches!(
+        codec
+            .with_max_depth(8)
+            .decode::<CborValue>(&bytes, &limits()),
+        Err(DecodeError::Cbor(_))
+    ));
+}
+
+#[test]
+fn cbor_decode_rejects_trailing_bytes() {
+    let mut bytes = to_cbor(&Value::Integer(1.into()));
+    bytes.push(0xf6);
+    assert!(matches!(
+        CborCodec::new(3, 1024).decode::<CborValue>(&bytes, &limits()),
+        Err(DecodeError::Cbor(_))
+    ));
+}
+
+#[test]
+fn cbor_encode_respects_output_limit() {
+    match CborCodec::new(3, 1024).encode(&"a long string value", &EncodeLimits::new(4)) {
+        Err(DecodeError::LimitExceeded { context, .. }) => {
+            assert_eq!(context, "codec_output_bytes")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -0,0 +1,121 @@
+use pg_debyte_core::action::{ActionSpec, ByteAction, ZstdAction};
+use pg_debyte_core::codec::{BincodeCodec, Codec};
+use pg_debyte_core::compact::{try_parse_compact, CompactEnvelopeBuilder};
+use pg_debyte_core::encode::{encode_to_compact_envelope, encode_to_envelope};
+use pg_debyte_core::envelope::{try_parse, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::StaticRegistry;
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Demo {
+    id: u32,
+    name: String,
+}
+
+const TYPE_ID: Uuid = Uuid::from_bytes([8; 16]);
+
+static ACTION: ZstdAction = ZstdAction::new(300);
+static ACTIONS: [&'static dyn ByteAction; 1] = [&ACTION];
+static REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &ACTIONS).with_type_aliases(&[(1000, TYPE_ID)]);
+
+#[test]
+fn compact_roundtrip_with_alias() {
+    let demo = Demo {
+        id: 3,
+        name: "compact".to_string(),
+    };
+    let key = TypeKey {
+        type_id: TYPE_ID,
+        schema_version: 200,
+    };
+    let codec = BincodeCodec::new(500, 1024);
+    let limits = EncodeLimits::new(1024);
+    let actions = vec![ActionSpec::new(300, 2, vec![3])];
+
+    let compact = encode_to_compact_envelope(&demo, &codec, key, &actions, &REGISTRY, &limits)
+        .expect("encode compact");
+    let full =
+        encode_to_envelope(&demo, &codec, key, &actions, &REGISTRY, &limits).expect("encode full");
+    assert!(compact.len() + 20 < full.len());
+    assert!(matches!(
+        try_parse(&compact).expect("parse"),
+        ParsedEnvelope::None
+    ));
+
+    let view = try_parse_compact(&compact)
+        .expect("parse")
+        .expect("compact envelope");
+    assert_eq!(view.alias, 1000);
+    assert_eq!(view.resolve_key(&REGISTRY).expect("resolve"), key);
+    assert_eq!(view.codec_id, 500);
+    let action = view.actions.get(0).expect("action");
+    assert_eq!((action.id, action.flags, action.params), (300, 2, &[3][..]));
+
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    let payload = ACTION
+        .decode(view.payload, &limits, action.params)
+        .expect("decompress");
+    let decoded: Demo = codec.decode(&payload, &limits).expect("decode");
+    assert_eq!(decoded, demo);
+}
+
+#[test]
+fn compact_encode_requires_registered_alias() {
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([9; 16]),
+        schema_version: 1,
+    };
+    let codec = BincodeCodec::new(1, 1024);
+    let err =
+        encode_to_compact_envelope(&1u32, &codec, key, &[], &REGISTRY, &EncodeLimits::new(1024))
+            .expect_err("expected error");
+    match err {
+        DecodeError::UnknownType(missing) => assert_eq!(missing, key),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn compact_unknown_alias() {
+    let bytes = CompactEnvelopeBuilder::new(7, 1, 1)
+        .build(b"payload")
+        .expect("build");
+    let view = try_parse_compact(&bytes)
+        .expect("parse")
+        .expect("compact envelope");
+    match view.resolve_key(&REGISTRY).expect_err("expected error") {
+        DecodeError::UnknownTypeAlias(alias) => assert_eq!(alias, 7),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn compact_params_out_of_bounds() {
+    let actions = vec![ActionSpec::new(1, 0, vec![1, 2, 3])];
+    let bytes = CompactEnvelopeBuilder::new(1, 1, 1)
+        .actions(&actions)
+        .build(&[])
+        .expect("build");
+
+    let err = try_parse_compact(&bytes[..bytes.len() - 1]).expect_err("expected error");
+    match err {
+        DecodeError::BadEnvelope(msg) => assert_eq!(msg, "params out of bounds"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn compact_rejects_oversized_varint() {
+    let mut bytes = b"PGDC\x01".to_vec();
+    bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x7f]);
+
+    let err = try_parse_compact(&bytes).expect_err("expected error");
+    match err {
+        DecodeError::BadEnvelope(msg) => assert_eq!(msg, "varint out of range"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
@@ -0,0 +1,339 @@
+use pg_debyte_core::action::{decode_actions, ActionSpec, ByteAction};
+use pg_debyte_core::codec::BincodeCodec;
+use pg_debyte_core::encode::encode_to_envelope;
+use pg_debyte_core::envelope::{try_parse, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::Registry;
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use uuid::Uuid;
+
+const PAYLOAD: &[u8] = b"hello hello hello hello hello hello hello hello";
+
+#[allow(dead_code)]
+fn assert_roundtrip(action: &dyn ByteAction, params: &[u8]) -> Vec<u8> {
+    let encoded = action
+        .encode(PAYLOAD, &EncodeLimits::new(1024), params)
+        .expect("encode");
+    let decoded = action
+        .decode(&encoded, &DecodeLimits::new(1024, 1024, 1024), params)
+        .expect("decode");
+    assert_eq!(decoded, PAYLOAD);
+    encoded
+}
+
+#[allow(dead_code)]
+fn assert_limits(action: &dyn ByteAction, encoded: &[u8]) {
+    let err = action
+        .decode(encoded, &DecodeLimits::new(1024, 8, 1024), &[])
+        .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, limit, .. } => {
+            assert_eq!(context, "action_output_bytes");
+            assert_eq!(limit, 8);
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    let err = action
+        .encode(PAYLOAD, &EncodeLimits::new(4), &[])
+        .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "action_output_bytes"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+/// Encodes an envelope through `registry` and undoes its actions again.
+#[allow(dead_code)]
+fn assert_envelope_roundtrip(registry: &dyn Registry, actions: &[ActionSpec]) {
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([4; 16]),
+        schema_version: 1,
+    };
+    let value = String::from_utf8(PAYLOAD.to_vec()).unwrap();
+    let codec = BincodeCodec::new(1, 1024);
+    let envelope = encode_to_envelope(
+        &value,
+        &codec,
+        key,
+        actions,
+        registry,
+        &EncodeLimits::new(1024),
+    )
+    .expect("encode");
+    let view = match try_parse(&envelope).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+    let payload = decode_actions(
+        registry,
+        view.actions,
+        view.payload,
+        &DecodeLimits::new(1024, 1024, 1024),
+    )
+    .expect("decode actions");
+    let plain = pg_debyte_core::codec::Codec::encode(&codec, &value, &EncodeLimits::new(1024))
+        .expect("encode plain");
+    assert_eq!(payload.as_ref(), plain.as_slice());
+}
+
+#[cfg(feature = "gzip")]
+mod gzip {
+    use super::*;
+    use pg_debyte_core::action::{DeflateAction, GzipAction};
+    use pg_debyte_core::registry::StaticRegistry;
+    use std::io::Write;
+
+    #[test]
+    fn gzip_roundtrip_with_levels() {
+        let action = GzipAction::new(2);
+        let stored = assert_roundtrip(&action, &[0]);
+        let best = assert_roundtrip(&action, &[9]);
+        assert_roundtrip(&action, &[]);
+        assert_eq!(&best[..2], &[0x1f, 0x8b]);
+        assert!(best.len() < stored.len());
+    }
+
+    #[test]
+    fn gzip_reads_foreign_multi_member_streams() {
+        let mut first = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        first.write_all(b"hello ").unwrap();
+        let mut stream = first.finish().unwrap();
+        let mut second = flate2::GzBuilder::new()
+            .filename("row.bin")
+            .write(Vec::new(), flate2::Compression::best());
+        second.write_all(b"world").unwrap();
+        stream.extend(second.finish().unwrap());
+
+        let decoded = GzipAction::new(2)
+            .decode(&stream, &DecodeLimits::new(1024, 1024, 1024), &[])
+            .expect("decode");
+        assert_eq!(decoded, b"hello world");
+    }
+
+    #[test]
+    fn deflate_roundtrip_is_raw() {
+        let action = DeflateAction::new(3);
+        let encoded = assert_roundtrip(&action, &[6]);
+        let mut decoder = flate2::write::DeflateDecoder::new(Vec::new());
+        decoder.write_all(&encoded).unwrap();
+        assert_eq!(decoder.finish().unwrap(), PAYLOAD);
+    }
+
+    #[test]
+    fn flate_actions_respect_limits() {
+        let gzip = GzipAction::new(2);
+        assert_limits(&gzip, &assert_roundtrip(&gzip, &[]));
+        let deflate = DeflateAction::new(3);
+        assert_limits(&deflate, &assert_roundtrip(&deflate, &[]));
+    }
+
+    #[test]
+    fn flate_actions_reject_bad_input() {
+        let limits = DecodeLimits::new(1024, 1024, 1024);
+        match GzipAction::new(2).decode(b"not gzip", &limits, &[]) {
+            Err(DecodeError::Deflate(_)) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+        match GzipAction::new(2).encode(PAYLOAD, &EncodeLimits::new(1024), &[10]) {
+            Err(DecodeError::InvalidActionParams { id: 2, .. }) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn flate_actions_register_in_static_registry() {
+        static GZIP: GzipAction = GzipAction::new(2);
+        static DEFLATE: DeflateAction = DeflateAction::new(3);
+        static REGISTRY: StaticRegistry = StaticRegistry::new(&[], &[&GZIP, &DEFLATE]);
+
+        assert_envelope_roundtrip(
+            &REGISTRY,
+            &[
+                ActionSpec::new(2, 0, vec![9]),
+                ActionSpec::new(3, 0, vec![]),
+            ],
+        );
+    }
+}
+
+#[cfg(feature = "lz4")]
+mod lz4 {
+    use super::*;
+    use pg_debyte_core::action::{Lz4Action, Lz4Format};
+    use pg_debyte_core::registry::StaticRegistry;
+
+    const BLOCK: Lz4Action = Lz4Action::new(4).with_format(Lz4Format::Block);
+
+    #[test]
+    fn lz4_frame_roundtrip() {
+        let action = Lz4Action::new(4);
+        let encoded = assert_roundtrip(&action, &[]);
+        assert_eq!(&encoded[..4], &[0x04, 0x22, 0x4d, 0x18]);
+        assert_limits(&action, &encoded);
+    }
+
+    #[test]
+    fn lz4_block_roundtrip_with_size_params() {
+        let params = BLOCK.encode_params(PAYLOAD, &[]).expect("params");
+        assert_eq!(params, (PAYLOAD.len() as u32).to_le_bytes());
+        let encoded = assert_roundtrip(&BLOCK, &params);
+
+        let err = BLOCK
+            .decode(
+                &encoded,
+                &DecodeLimits::new(1024, 1024, 1024),
+                &[1, 0, 0, 0],
+            )
+            .expect_err("expected size mismatch");
+        assert!(
+            matches!(err, DecodeError::Lz4(_)),
+            "unexpected error: {err:?}"
+        );
+    }
+
+    #[test]
+    fn lz4_block_checks_size_before_allocating() {
+        let limits = DecodeLimits::new(1024, 1024, 1024);
+        let err = BLOCK
+            .decode(&[0x00], &limits, &u32::MAX.to_le_bytes())
+            .expect_err("expected limit error");
+        match err {
+            DecodeError::LimitExceeded {
+                context, actual, ..
+            } => {
+                assert_eq!(context, "action_output_bytes");
+                assert_eq!(actual, u32::MAX as usize);
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        match BLOCK.decode(&[0x00], &limits, &[]) {
+            Err(DecodeError::InvalidActionParams { id: 4, .. }) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lz4_block_records_size_in_envelope() {
+        static BLOCK_ACTION: Lz4Action = BLOCK;
+        static REGISTRY: StaticRegistry = StaticRegistry::new(&[], &[&BLOCK_ACTION]);
+
+        assert_envelope_roundtrip(&REGISTRY, &[ActionSpec::new(4, 0, vec![])]);
+    }
+}
+
+#[cfg(feature = "snappy")]
+mod snappy {
+    use super::*;
+    use pg_debyte_core::action::{SnappyAction, SnappyFormat};
+    use pg_debyte_core::registry::StaticRegistry;
+
+    #[test]
+    fn snappy_raw_roundtrip() {
+        let action = SnappyAction::new(5);
+        let encoded = assert_roundtrip(&action, &[]);
+        assert_eq!(encoded[0] as usize, PAYLOAD.len());
+        assert_limits(&action, &encoded);
+    }
+
+    #[test]
+    fn snappy_frame_roundtrip() {
+        let action = SnappyAction::new(5).with_format(SnappyFormat::Frame);
+        let encoded = assert_roundtrip(&action, &[]);
+        assert_eq!(&encoded[..4], &[0xff, 0x06, 0x00, 0x00]);
+        assert_limits(&action, &encoded);
+    }
+
+    #[test]
+    fn snappy_raw_checks_size_before_allocating() {
+        // Varint preamble claiming 2^28 bytes with no data behind it.
+        let err = SnappyAction::new(5)
+            .decode(
+                &[0x80, 0x80, 0x80, 0x80, 0x01],
+                &DecodeLimits::new(1024, 1024, 1024),
+                &[],
+            )
+            .expect_err("expected limit error");
+        match err {
+            DecodeError::LimitExceeded {
+                context, actual, ..
+            } => {
+                assert_eq!(context, "action_output_bytes");
+                assert_eq!(actual, 1 << 28);
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn snappy_registers_in_static_registry() {
+        static SNAPPY: SnappyAction = SnappyAction::new(5);
+        static REGISTRY: StaticRegistry = StaticRegistry::new(&[], &[&SNAPPY]);
+
+        assert_envelope_roundtrip(&REGISTRY, &[ActionSpec::new(5, 0, vec![])]);
+    }
+}
+
+#[cfg(feature = "brotli")]
+mod brotli {
+    use super::*;
+    use pg_debyte_core::action::BrotliAction;
+    use pg_debyte_core::registry::StaticRegistry;
+
+    #[test]
+    fn brotli_roundtrip_with_quality_and_window() {
+        let action = BrotliAction::new(6);
+        let fast = assert_roundtrip(&action, &[0, 10]);
+        let best = assert_roundtrip(&action, &[11, 24]);
+        assert_roundtrip(&action, &[5]);
+        assert_roundtrip(&action, &[]);
+        assert!(best.len() <= fast.len());
+        assert_limits(&action, &best);
+    }
+
+    #[test]
+    fn brotli_rejects_bad_params() {
+        let action = BrotliAction::new(6);
+        for params in [&[12][..], &[11, 9], &[11, 25]] {
+            match action.encode(PAYLOAD, &EncodeLimits::new(1024), params) {
+                Err(DecodeError::InvalidActionParams { id: 6, .. }) => {}
+                other => panic!("unexpected result for {params:?}: {other:?}"),
+            }
+        }
+        match action.decode(b"not brotli", &DecodeLimits::new(1024, 1024, 1024), &[]) {
+            Err(DecodeError::Brotli(_)) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn brotli_stops_streaming_at_output_limit() {
+        let action = BrotliAction::new(6);
+        let bomb = action
+            .encode(&vec![0u8; 1 << 20], &EncodeLimits::new(1 << 20), &[])
+            .expect("encode");
+        assert!(bomb.len() < 1024);
+
+        let err = action
+            .decode(&bomb, &DecodeLimits::new(1024, 4096, 1024), &[])
+            .expect_err("expected limit error");
+        match err {
+            DecodeError::LimitExceeded {
+                context, actual, ..
+            } => {
+                assert_eq!(context, "action_output_bytes");
+                assert!(actual <= 4096 + 8192, "read too far: {actual}");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn brotli_registers_in_static_registry() {
+        static BROTLI: BrotliAction = BrotliAction::new(6);
+        static REGISTRY: StaticRegistry = StaticRegistry::new(&[], &[&BROTLI]);
+
+        assert_envelope_roundtrip(&REGISTRY, &[ActionSpec::new(6, 0, vec![9, 20])]);
+    }
+}
@@ -7,7 +7,9 @@ use pg_debyte_core::types::EncodeLimits;
 use pg_debyte_core::ByteAction;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize,
+)]
 struct Demo {
     id: u32,
     name: String,
@@ -99,3 +101,172 @@ fn bincode_encode_respects_limits() {
         other => panic!("unexpected error: {other:?}"),
     }
 }
+
+#[cfg(feature = "postcard")]
+#[test]
+fn postcard_decode_roundtrip() {
+    use pg_debyte_core::codec::PostcardCodec;
+
+    let demo = Demo {
+        id: 1,
+        name: "demo".to_string(),
+    };
+    let bytes = postcard::to_allocvec(&demo).expect("serialize");
+    let codec = PostcardCodec::new(1, 1024);
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    let decoded: Demo = codec.decode(&bytes, &limits).expect("decode");
+    assert_eq!(decoded, demo);
+    assert_eq!(
+        codec
+            .encode(&demo, &EncodeLimits::new(1024))
+            .expect("encode"),
+        bytes
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    match codec.decode::<Demo>(&trailing, &limits) {
+        Err(DecodeError::Postcard(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn postcard_respects_limits() {
+    use pg_debyte_core::codec::PostcardCodec;
+
+    let codec = PostcardCodec::new(1, 16);
+    let err = codec
+        .decode::<Vec<u8>>(&[0u8; 64], &DecodeLimits::new(1024, 1024, 1024))
+        .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "codec_input_bytes"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    let demo = Demo {
+        id: 1,
+        name: "demo".repeat(64),
+    };
+    let err = codec
+        .encode(&demo, &EncodeLimits::new(1024))
+        .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "codec_output_bytes"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn borsh_decode_roundtrip() {
+    use pg_debyte_core::codec::BorshCodec;
+
+    let demo = Demo {
+        id: 1,
+        name: "demo".to_string(),
+    };
+    let codec = BorshCodec::new(1, 1024);
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    let bytes = codec
+        .encode(&demo, &EncodeLimits::new(1024))
+        .expect("encode");
+    assert_eq!(bytes, [1, 0, 0, 0, 4, 0, 0, 0, b'd', b'e', b'm', b'o']);
+    let decoded: Demo = codec.decode(&bytes, &limits).expect("decode");
+    assert_eq!(decoded, demo);
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn borsh_matches_borsh_crate() {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use pg_debyte_core::codec::BorshCodec;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize, BorshDeserialize, BorshSerialize)]
+    enum Event {
+        Ping,
+        Transfer { from: [u8; 4], amount: u128 },
+        Memo(String),
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize, BorshDeserialize, BorshSerialize)]
+    struct Block {
+        height: u64,
+        delta: i16,
+        ratio: f64,
+        parent: Option<[u8; 4]>,
+        events: Vec<Event>,
+        labels: BTreeMap<String, u32>,
+        flag: bool,
+        unit: (),
+    }
+
+    let block = Block {
+        height: 42,
+        delta: -3,
+        ratio: 0.5,
+        parent: Some([1, 2, 3, 4]),
+        events: vec![
+            Event::Ping,
+            Event::Transfer {
+                from: [9, 9, 9, 9],
+                amount: u128::MAX,
+            },
+            Event::Memo("hi".to_string()),
+        ],
+        labels: BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+        flag: true,
+        unit: (),
+    };
+    let codec = BorshCodec::new(1, 1024);
+    let expected = borsh::to_vec(&block).expect("borsh");
+    assert_eq!(
+        codec
+            .encode(&block, &EncodeLimits::new(1024))
+            .expect("encode"),
+        expected
+    );
+    let decoded: Block = codec
+        .decode(&expected, &DecodeLimits::new(1024, 1024, 1024))
+        .expect("decode");
+    assert_eq!(decoded, block);
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn borsh_respects_limits() {
+    use pg_debyte_core::codec::BorshCodec;
+
+    let codec = BorshCodec::new(1, 16);
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    let err = codec
+        .decode::<Vec<u8>>(&[0u8; 64], &limits)
+        .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "codec_input_bytes"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    // A length prefix larger than the limit is rejected before reading.
+    let err = codec
+        .decode::<Vec<()>>(&[0xff, 0xff, 0xff, 0xff], &limits)
+        .expect_err("expected length error");
+    match err {
+        DecodeError::Borsh(_) => {}
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    let demo = Demo {
+        id: 1,
+        name: "demo".repeat(64),
+    };
+    let err = codec
+        .encode(&demo, &EncodeLimits::new(1024))
+        .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "codec_output_bytes"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
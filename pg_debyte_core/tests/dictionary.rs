@@ -0,0 +1,196 @@
+use pg_debyte_core::action::{decode_actions, ActionSpec, ByteAction, ZstdAction};
+use pg_debyte_core::codec::{BincodeCodec, Codec};
+use pg_debyte_core::dictionary::{
+    train_zstd_dictionary, Dictionary, DictionaryProvider, StaticDictionaryProvider,
+};
+use pg_debyte_core::encode::encode_to_envelope;
+use pg_debyte_core::envelope::{try_parse, EnvelopeView, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::StaticRegistry;
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Event {
+    id: u32,
+    kind: String,
+    source: String,
+    status: String,
+}
+
+const DICTIONARY_ID: u32 = 5;
+
+/// Serves a dictionary trained on first use under [`DICTIONARY_ID`].
+struct TrainedDictionaries;
+
+impl DictionaryProvider for TrainedDictionaries {
+    fn dictionary(&self, id: u32) -> Result<Option<Dictionary>, DecodeError> {
+        Ok((id == DICTIONARY_ID).then(|| Dictionary::from(trained().as_slice())))
+    }
+}
+
+static ZSTD: ZstdAction = ZstdAction::new(1);
+static DICTIONARIES: TrainedDictionaries = TrainedDictionaries;
+static REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &[&ZSTD]).with_dictionary_provider(&DICTIONARIES);
+static NO_DICTIONARIES: StaticRegistry = StaticRegistry::new(&[], &[&ZSTD]);
+
+fn event(id: u32) -> Event {
+    Event {
+        id,
+        kind: "order.created".to_string(),
+        source: format!("checkout-service-{}", id % 4),
+        status: ["pending", "confirmed"][id as usize % 2].to_string(),
+    }
+}
+
+fn codec() -> BincodeCodec {
+    BincodeCodec::new(1, 4096)
+}
+
+fn samples() -> Vec<Vec<u8>> {
+    (0..1000)
+        .map(|id| {
+            codec()
+                .encode(&event(id), &EncodeLimits::new(4096))
+                .unwrap()
+        })
+        .collect()
+}
+
+fn trained() -> &'static Vec<u8> {
+    static TRAINED: OnceLock<Vec<u8>> = OnceLock::new();
+    TRAINED.get_or_init(|| train_zstd_dictionary(&samples(), 1024).expect("train"))
+}
+
+fn key() -> TypeKey {
+    TypeKey {
+        type_id: Uuid::from_bytes([0xd1; 16]),
+        schema_version: 1,
+    }
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(4096, 4096, 4096)
+}
+
+fn compress(registry: &StaticRegistry, params: Vec<u8>) -> Result<Vec<u8>, DecodeError> {
+    encode_to_envelope(
+        &event(4242),
+        &codec(),
+        key(),
+        &[ActionSpec::new(1, 0, params)],
+        registry,
+        &EncodeLimits::new(4096),
+    )
+}
+
+fn view(envelope: &[u8]) -> EnvelopeView<'_> {
+    match try_parse(envelope).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    }
+}
+
+#[test]
+fn zstd_dictionary_roundtrip() {
+    let params = ZstdAction::dictionary_params(3, DICTIONARY_ID);
+    assert_eq!(params, [3, 5, 0, 0, 0]);
+    let envelope = compress(&REGISTRY, params.clone()).expect("encode");
+    let view = view(&envelope);
+    assert_eq!(
+        view.actions.get(0).expect("action").params,
+        params.as_slice()
+    );
+
+    let payload = decode_actions(&REGISTRY, view.actions, view.payload, &limits()).expect("decode");
+    let decoded: Event = codec().decode(&payload, &limits()).expect("codec");
+    assert_eq!(decoded, event(4242));
+}
+
+#[test]
+fn zstd_dictionary_shrinks_small_payloads() {
+    let plain = compress(&REGISTRY, vec![3]).expect("encode");
+    let with_dictionary =
+        compress(&REGISTRY, ZstdAction::dictionary_params(3, DICTIONARY_ID)).expect("encode");
+    assert!(view(&with_dictionary).payload.len() < view(&plain).payload.len());
+}
+
+#[test]
+fn zstd_dictionary_must_be_registered() {
+    let params = ZstdAction::dictionary_params(3, 6);
+    match compress(&REGISTRY, params) {
+        Err(DecodeError::UnknownDictionary(6)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let envelope =
+        compress(&REGISTRY, ZstdAction::dictionary_params(3, DICTIONARY_ID)).expect("encode");
+    let view = view(&envelope);
+    match decode_actions(&NO_DICTIONARIES, view.actions, view.payload, &limits()) {
+        Err(DecodeError::UnknownDictionary(DICTIONARY_ID)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    // Without a registry there is nowhere to look the dictionary up.
+    match ZSTD.decode(view.payload, &limits(), view.actions.get(0).unwrap().params) {
+        Err(DecodeError::UnknownDictionary(DICTIONARY_ID)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn zstd_dictionary_decode_respects_limit() {
+    let envelope =
+        compress(&REGISTRY, ZstdAction::dictionary_params(3, DICTIONARY_ID)).expect("encode");
+    let view = view(&envelope);
+    match decode_actions(
+        &REGISTRY,
+        view.actions,
+        view.payload,
+        &DecodeLimits::new(4096, 8, 4096),
+    ) {
+        Err(DecodeError::LimitExceeded { limit: 8, .. }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn zstd_rejects_malformed_params() {
+    match compress(&REGISTRY, vec![3, 5, 0]) {
+        Err(DecodeError::InvalidActionParams { id: 1, .. }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn zstd_decode_ignores_legacy_trailing_params() {
+    let envelope = compress(&REGISTRY, vec![3]).expect("encode");
+    let payload = view(&envelope).payload;
+    for params in [&[3, 5, 0][..], &[3, 1, 2, 3, 4, 5]] {
+        let decoded = ZSTD.decode(payload, &limits(), params).expect("decode");
+        let decoded: Event = codec().decode(&decoded, &limits()).expect("codec");
+        assert_eq!(decoded, event(4242));
+    }
+}
+
+#[test]
+fn static_dictionary_provider_looks_up_by_id() {
+    static PROVIDER: StaticDictionaryProvider = StaticDictionaryProvider::new(&[(1, b"abc")]);
+    assert_eq!(
+        PROVIDER.dictionary(1).expect("lookup").as_deref(),
+        Some(&b"abc"[..])
+    );
+    assert!(PROVIDER.dictionary(2).expect("lookup").is_none());
+}
+
+#[test]
+fn train_rejects_empty_samples() {
+    let samples: [Vec<u8>; 0] = [];
+    match train_zstd_dictionary(&samples, 1024) {
+        Err(DecodeError::InvalidDictionary(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -0,0 +1,144 @@
+use pg_debyte_core::codec::{BincodeCodec, CborCodec, Codec, RmpCodec};
+use pg_debyte_core::dyn_codec::{decode_dyn, DynCodec};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::{DecoderEntry, Registry, StaticRegistry, TypedDecoderEntry};
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+enum Shape {
+    Point,
+    Circle(f64),
+    Rect { w: u32, h: u32 },
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Demo {
+    id: u64,
+    delta: i32,
+    name: String,
+    parent: Option<u32>,
+    shapes: Vec<Shape>,
+}
+
+const KEY: TypeKey = TypeKey {
+    type_id: Uuid::from_bytes([8; 16]),
+    schema_version: 1,
+};
+
+static BINCODE: BincodeCodec = BincodeCodec::new(1, 1024);
+static RMP: RmpCodec = RmpCodec::new(2, 1024);
+static CBOR: CborCodec = CborCodec::new(3, 1024);
+
+fn demo() -> Demo {
+    Demo {
+        id: 7,
+        delta: -2,
+        name: "demo".to_string(),
+        parent: None,
+        shapes: vec![Shape::Point, Shape::Circle(1.5), Shape::Rect { w: 2, h: 3 }],
+    }
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(1024, 1024, 1024)
+}
+
+fn assert_dyn_roundtrip<C: Codec + DynCodec>(codec: &C) {
+    let encode_limits = EncodeLimits::new(1024);
+    let bytes = Codec::encode(codec, &demo(), &encode_limits).expect("encode");
+    let erased: &dyn DynCodec = codec;
+    assert_eq!(
+        erased
+            .encode_erased(&demo(), &encode_limits)
+            .expect("encode"),
+        bytes
+    );
+    let decoded: Demo = decode_dyn(erased, &bytes, &limits()).expect("decode");
+    assert_eq!(decoded, demo());
+}
+
+#[test]
+fn dyn_codecs_roundtrip() {
+    assert_dyn_roundtrip(&BINCODE);
+    assert_dyn_roundtrip(&RMP);
+    assert_dyn_roundtrip(&CBOR);
+    #[cfg(feature = "bincode2")]
+    assert_dyn_roundtrip(&pg_debyte_core::codec::Bincode2Codec::<1024>::new(4));
+    #[cfg(feature = "postcard")]
+    assert_dyn_roundtrip(&pg_debyte_core::codec::PostcardCodec::new(5, 1024));
+}
+
+#[test]
+fn dyn_codecs_keep_limits_and_errors() {
+    let bytes = RMP
+        .encode(&demo(), &EncodeLimits::new(1024))
+        .expect("encode");
+    match decode_dyn::<Demo>(&RMP, &bytes, &DecodeLimits::new(1024, 8, 1024)) {
+        Err(DecodeError::LimitExceeded { context, .. }) => assert_eq!(context, "codec_input_bytes"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    match decode_dyn::<Demo>(&RMP, &trailing, &limits()) {
+        Err(DecodeError::MessagePack(message)) => assert!(message.contains("trailing")),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    match decode_dyn::<Demo>(&CBOR, &[0x01], &limits()) {
+        Err(DecodeError::Cbor(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn registry_looks_up_codecs_by_id() {
+    static REGISTRY: StaticRegistry =
+        StaticRegistry::new(&[], &[]).with_codecs(&[&BINCODE, &RMP, &CBOR]);
+
+    assert_eq!(REGISTRY.lookup_codec(2).expect("codec").id(), 2);
+    assert!(REGISTRY.lookup_codec(9).is_none());
+    assert!(StaticRegistry::new(&[], &[]).lookup_codec(1).is_none());
+}
+
+#[test]
+fn typed_entry_decodes_registered_codecs() {
+    static STRICT: TypedDecoderEntry<Demo, BincodeCodec> =
+        TypedDecoderEntry::new(KEY, BincodeCodec::new(1, 1024), &[]);
+    static MIGRATING: TypedDecoderEntry<Demo, BincodeCodec> =
+        TypedDecoderEntry::new(KEY, BincodeCodec::new(1, 1024), &[]).with_registered_codecs();
+
+    let bytes = RMP
+        .encode(&demo(), &EncodeLimits::new(1024))
+        .expect("encode");
+    let expected = json!({
+        "id": 7,
+        "delta": -2,
+        "name": "demo",
+        "parent": null,
+        "shapes": ["Point", {"Circle": 1.5}, {"Rect": {"w": 2, "h": 3}}],
+    });
+
+    assert!(STRICT.accepts_codec(1));
+    assert!(!STRICT.accepts_codec(2));
+    match STRICT.decode_payload_with(&RMP, &bytes, &limits()) {
+        Err(DecodeError::UnknownCodec(2)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    assert!(MIGRATING.accepts_codec(2));
+    let value = MIGRATING
+        .decode_payload_with(&RMP, &bytes, &limits())
+        .expect("decode");
+    assert_eq!(value, expected);
+    let json = MIGRATING
+        .decode_payload_json_with(&RMP, &bytes, &limits())
+        .expect("decode");
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&json).unwrap(),
+        expected
+    );
+}
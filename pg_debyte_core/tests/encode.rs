@@ -7,7 +7,9 @@ use pg_debyte_core::types::{EncodeLimits, TypeKey};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize,
+)]
 struct Demo {
     id: u32,
     name: String,
@@ -79,9 +81,10 @@ fn encode_roundtrip_with_actions() {
     assert_eq!(view.key, key);
     assert_eq!(view.codec_id, codec.id());
     assert_eq!(view.actions.len(), 1);
-    assert_eq!(view.actions[0].id, 7);
-    assert_eq!(view.actions[0].flags, 1);
-    assert_eq!(view.actions[0].params, [1]);
+    let action = view.actions.get(0).expect("action");
+    assert_eq!(action.id, 7);
+    assert_eq!(action.flags, 1);
+    assert_eq!(action.params, [1]);
 }
 
 #[test]
@@ -106,3 +109,55 @@ fn encode_rejects_unknown_action() {
         other => panic!("unexpected error: {other:?}"),
     }
 }
+
+#[cfg(feature = "postcard")]
+#[test]
+fn encode_builds_envelope_with_postcard() {
+    use pg_debyte_core::codec::PostcardCodec;
+
+    assert_envelope_roundtrip(&PostcardCodec::new(4, 1024));
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn encode_builds_envelope_with_borsh() {
+    use pg_debyte_core::codec::BorshCodec;
+
+    assert_envelope_roundtrip(&BorshCodec::new(5, 1024));
+}
+
+#[cfg(any(feature = "postcard", feature = "borsh"))]
+fn assert_envelope_roundtrip<C>(codec: &C)
+where
+    C: pg_debyte_core::codec::PayloadEncoder<Demo> + pg_debyte_core::codec::PayloadDecoder<Demo>,
+{
+    let demo = Demo {
+        id: 11,
+        name: "codec".to_string(),
+    };
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([5; 16]),
+        schema_version: 1,
+    };
+    let registry = StaticRegistry::new(&[], &[]);
+    let encoded = encode_to_envelope(&demo, codec, key, &[], &registry, &EncodeLimits::new(1024))
+        .expect("encode");
+
+    let view = match try_parse(&encoded).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+    assert_eq!(view.key, key);
+    assert_eq!(
+        view.codec_id,
+        pg_debyte_core::codec::PayloadEncoder::codec_id(codec)
+    );
+
+    let decoded: Demo = codec
+        .decode_payload(
+            view.payload,
+            &pg_debyte_core::DecodeLimits::new(1024, 1024, 1024),
+        )
+        .expect("decode payload");
+    assert_eq!(decoded, demo);
+}
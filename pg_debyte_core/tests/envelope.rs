@@ -1,4 +1,6 @@
-use pg_debyte_core::envelope::{try_parse, ParsedEnvelope};
+use pg_debyte_core::action::ActionSpec;
+use pg_debyte_core::envelope::{build_envelope, try_parse, try_parse_confluent, ParsedEnvelope};
+use pg_debyte_core::types::TypeKey;
 use uuid::Uuid;
 
 #[test]
@@ -27,15 +29,40 @@ fn envelope_parses() {
         ParsedEnvelope::None => panic!("expected envelope"),
     };
 
+    assert_eq!(view.version, 1);
     assert_eq!(view.key.type_id, type_id);
     assert_eq!(view.key.schema_version, schema_version);
     assert_eq!(view.codec_id, codec_id);
     assert_eq!(view.actions.len(), 1);
-    assert_eq!(view.actions[0].id, 9);
-    assert_eq!(view.actions[0].params, b"abc");
+    let action = view.actions.get(0).expect("action");
+    assert_eq!(action.id, 9);
+    assert_eq!(action.params, b"abc");
     assert_eq!(view.payload, payload);
 }
 
+#[test]
+fn envelope_v2_roundtrip() {
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([1; 16]),
+        schema_version: 42,
+    };
+    let actions = vec![ActionSpec::new(9, 0, b"abc".to_vec())];
+    let bytes = build_envelope(key, 7, &actions, b"payload").expect("build");
+
+    let parsed = try_parse(&bytes).expect("parse");
+    let view = match parsed {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+
+    assert_eq!(view.version, 2);
+    assert_eq!(view.key, key);
+    assert_eq!(view.codec_id, 7);
+    let parsed_actions: Vec<ActionSpec> = view.actions.iter().map(ActionSpec::from).collect();
+    assert_eq!(parsed_actions, actions);
+    assert_eq!(view.payload, b"payload");
+}
+
 #[test]
 fn envelope_missing_magic() {
     let bytes = b"not-an-envelope";
@@ -45,3 +72,64 @@ fn envelope_missing_magic() {
         ParsedEnvelope::Envelope(_) => panic!("expected none"),
     }
 }
+
+#[test]
+fn envelope_actions_borrow_input() {
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([1; 16]),
+        schema_version: 1,
+    };
+    let actions = vec![
+        ActionSpec::new(1, 0, b"first".to_vec()),
+        ActionSpec::new(2, 3, Vec::new()),
+        ActionSpec::new(3, 0, b"third".to_vec()),
+    ];
+    let bytes = build_envelope(key, 7, &actions, b"payload").expect("build");
+
+    let view = match try_parse(&bytes).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+
+    let headers: Vec<_> = view
+        .actions
+        .iter()
+        .map(|action| (action.id, action.flags, action.params))
+        .collect();
+    assert_eq!(
+        headers,
+        vec![
+            (1, 0, &b"first"[..]),
+            (2, 3, &b""[..]),
+            (3, 0, &b"third"[..])
+        ]
+    );
+    let input = bytes.as_ptr_range();
+    for action in view.actions {
+        assert!(input.contains(&action.params.as_ptr()));
+    }
+    assert_eq!(view.actions.get(2).map(|action| action.id), Some(3));
+    assert!(view.actions.get(3).is_none());
+}
+
+#[test]
+fn confluent_frame_parses() {
+    let mut bytes = vec![0];
+    bytes.extend_from_slice(&0x0102_0304u32.to_be_bytes());
+    bytes.extend_from_slice(b"payload");
+
+    assert!(matches!(
+        try_parse(&bytes).expect("parse"),
+        ParsedEnvelope::None
+    ));
+    let view = try_parse_confluent(&bytes).expect("confluent frame");
+    assert_eq!(view.schema_id, 0x0102_0304);
+    assert_eq!(view.payload, b"payload");
+}
+
+#[test]
+fn confluent_frame_requires_magic_and_schema_id() {
+    assert!(try_parse_confluent(&[0, 0, 0, 1]).is_none());
+    assert!(try_parse_confluent(&[1, 0, 0, 0, 1, 2]).is_none());
+    assert!(try_parse_confluent(b"PGDEBYTE").is_none());
+}
@@ -0,0 +1,114 @@
+use pg_debyte_core::action::ActionSpec;
+use pg_debyte_core::codec::BincodeCodec;
+use pg_debyte_core::encode::encode_to_envelope;
+use pg_debyte_core::envelope::{try_parse, EnvelopeBuilder, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::metadata::EnvelopeMetadata;
+use pg_debyte_core::registry::StaticRegistry;
+use pg_debyte_core::types::{EncodeLimits, TypeKey};
+use uuid::Uuid;
+
+fn key() -> TypeKey {
+    TypeKey {
+        type_id: Uuid::from_bytes([6; 16]),
+        schema_version: 3,
+    }
+}
+
+#[test]
+fn builder_rejects_too_many_actions() {
+    let actions = vec![ActionSpec::new(1, 0, Vec::new()); 256];
+    let err = EnvelopeBuilder::new(key(), 1)
+        .actions(&actions)
+        .build(b"payload")
+        .expect_err("expected error");
+    match err {
+        DecodeError::TooManyActions(count) => assert_eq!(count, 256),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn builder_rejects_oversized_params() {
+    let actions = vec![ActionSpec::new(4, 0, vec![0u8; 65536])];
+    let err = EnvelopeBuilder::new(key(), 1)
+        .actions(&actions)
+        .build(b"payload")
+        .expect_err("expected error");
+    match err {
+        DecodeError::ActionParamsTooLarge { id, len } => {
+            assert_eq!(id, 4);
+            assert_eq!(len, 65536);
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn builder_accepts_max_bounds() {
+    let actions = vec![ActionSpec::new(1, 0, vec![7u8; 65535]); 255];
+    let bytes = EnvelopeBuilder::new(key(), 1)
+        .actions(&actions)
+        .build(b"payload")
+        .expect("build");
+
+    let view = match try_parse(&bytes).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+    assert_eq!(view.actions.len(), 255);
+    assert!(view
+        .actions
+        .iter()
+        .all(|action| action.params.len() == 65535));
+    assert_eq!(view.payload, b"payload");
+}
+
+#[test]
+fn builder_writes_into_existing_buffer_and_writer() {
+    let metadata = EnvelopeMetadata::new().producer("writer");
+    let actions = vec![ActionSpec::new(2, 1, vec![3])];
+    let builder = EnvelopeBuilder::new(key(), 9)
+        .actions(&actions)
+        .metadata(&metadata);
+
+    let mut buffer = b"prefix".to_vec();
+    builder.build_into(&mut buffer, b"payload").expect("build");
+    let mut written = Vec::new();
+    let len = builder.write_to(&mut written, b"payload").expect("write");
+
+    assert_eq!(len, written.len());
+    assert_eq!(&buffer[..6], b"prefix");
+    assert_eq!(&buffer[6..], &written[..]);
+
+    let view = match try_parse(&written).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+    assert_eq!(view.version, 3);
+    assert_eq!(view.key, key());
+    assert_eq!(view.codec_id, 9);
+    assert_eq!(view.metadata.producer(), Some("writer"));
+    assert_eq!(view.payload, b"payload");
+}
+
+#[test]
+fn encode_rejects_too_many_actions_before_encoding() {
+    let codec = BincodeCodec::new(1, 1024);
+    let registry = StaticRegistry::new(&[], &[]);
+    let actions = vec![ActionSpec::new(99, 0, Vec::new()); 300];
+
+    let err = encode_to_envelope(
+        &1u32,
+        &codec,
+        key(),
+        &actions,
+        &registry,
+        &EncodeLimits::new(1024),
+    )
+    .expect_err("expected error");
+    match err {
+        DecodeError::TooManyActions(count) => assert_eq!(count, 300),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
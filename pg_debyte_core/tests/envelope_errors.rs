@@ -1,5 +1,6 @@
-use pg_debyte_core::envelope::try_parse;
+use pg_debyte_core::envelope::{build_envelope, try_parse, try_parse_unverified, ParsedEnvelope};
 use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::types::TypeKey;
 use uuid::Uuid;
 
 fn base_header(version: u8, actions_count: u8) -> Vec<u8> {
@@ -15,7 +16,7 @@ fn base_header(version: u8, actions_count: u8) -> Vec<u8> {
 
 #[test]
 fn envelope_unsupported_version() {
-    let bytes = base_header(2, 0);
+    let bytes = base_header(9, 0);
     let err = try_parse(&bytes).expect_err("expected error");
     match err {
         DecodeError::BadEnvelope(msg) => assert_eq!(msg, "unsupported envelope version"),
@@ -47,3 +48,54 @@ fn envelope_params_out_of_bounds() {
         other => panic!("unexpected error: {other:?}"),
     }
 }
+
+#[test]
+fn envelope_v2_checksum_out_of_bounds() {
+    let bytes = base_header(2, 0);
+    let err = try_parse(&bytes).expect_err("expected error");
+    match err {
+        DecodeError::BadEnvelope(msg) => assert_eq!(msg, "checksum out of bounds"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn envelope_v2_detects_corrupted_payload() {
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([0x11; 16]),
+        schema_version: 1,
+    };
+    let mut bytes = build_envelope(key, 2, &[], b"payload").expect("build");
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0x01;
+
+    let err = try_parse(&bytes).expect_err("expected error");
+    match err {
+        DecodeError::ChecksumMismatch { expected, actual } => assert_ne!(expected, actual),
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    match try_parse_unverified(&bytes).expect("parse") {
+        ParsedEnvelope::Envelope(view) => {
+            assert_eq!(view.key, key);
+            assert!(view.checksum.is_some());
+            assert_eq!(view.payload, b"payloae");
+        }
+        ParsedEnvelope::None => panic!("expected envelope"),
+    }
+}
+
+#[test]
+fn envelope_v2_detects_truncated_payload() {
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([0x11; 16]),
+        schema_version: 1,
+    };
+    let bytes = build_envelope(key, 2, &[], b"payload").expect("build");
+
+    let err = try_parse(&bytes[..bytes.len() - 2]).expect_err("expected error");
+    match err {
+        DecodeError::ChecksumMismatch { .. } => {}
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
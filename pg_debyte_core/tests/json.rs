@@ -0,0 +1,80 @@
+use pg_debyte_core::codec::{BincodeCodec, Codec};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::json::{ensure_json_limit, to_json_bytes};
+use pg_debyte_core::registry::{DecoderEntry, TypedDecoderEntry};
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Demo {
+    id: u32,
+    name: String,
+}
+
+static DEMO_ENTRY: TypedDecoderEntry<Demo, BincodeCodec> = TypedDecoderEntry::new(
+    TypeKey {
+        type_id: Uuid::from_bytes([7; 16]),
+        schema_version: 1,
+    },
+    BincodeCodec::new(1, 1024),
+    &[],
+);
+
+fn demo_payload(name: &str) -> Vec<u8> {
+    let demo = Demo {
+        id: 1,
+        name: name.to_string(),
+    };
+    BincodeCodec::new(1, 1024)
+        .encode(&demo, &EncodeLimits::new(1024))
+        .expect("encode")
+}
+
+#[test]
+fn decode_payload_json_matches_value() {
+    let payload = demo_payload("demo");
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+
+    let json = DEMO_ENTRY
+        .decode_payload_json(&payload, &limits)
+        .expect("json");
+    assert_eq!(json, br#"{"id":1,"name":"demo"}"#);
+
+    let value = DEMO_ENTRY.decode_payload(&payload, &limits).expect("value");
+    assert_eq!(serde_json::to_vec(&value).unwrap(), json);
+}
+
+#[test]
+fn decode_payload_json_respects_limit() {
+    let payload = demo_payload(&"x".repeat(256));
+    let err = DEMO_ENTRY
+        .decode_payload_json(&payload, &DecodeLimits::new(1024, 1024, 32))
+        .expect_err("expected limit error");
+    match err {
+        DecodeError::LimitExceeded { context, limit, .. } => {
+            assert_eq!(context, "json_bytes");
+            assert_eq!(limit, 32);
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn json_helpers_share_limit_semantics() {
+    let value = serde_json::json!({"id": 1, "name": "demo"});
+    let exact = DecodeLimits::new(1024, 1024, 22);
+    assert_eq!(ensure_json_limit(&value, &exact).expect("fits"), 22);
+    assert_eq!(to_json_bytes(&value, &exact).expect("fits").len(), 22);
+
+    let short = DecodeLimits::new(1024, 1024, 21);
+    for err in [
+        ensure_json_limit(&value, &short).map(|_| ()).unwrap_err(),
+        to_json_bytes(&value, &short).map(|_| ()).unwrap_err(),
+    ] {
+        match err {
+            DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "json_bytes"),
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use pg_debyte_core::codec::BincodeCodec;
+use pg_debyte_core::encode::encode_to_envelope_with_metadata;
+use pg_debyte_core::envelope::{build_envelope_with_metadata, try_parse, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::metadata::{EnvelopeMetadata, MAX_METADATA_BYTES};
+use pg_debyte_core::registry::StaticRegistry;
+use pg_debyte_core::types::{EncodeLimits, TypeKey};
+use serde_json::json;
+use uuid::Uuid;
+
+fn key() -> TypeKey {
+    TypeKey {
+        type_id: Uuid::from_bytes([5; 16]),
+        schema_version: 1,
+    }
+}
+
+#[test]
+fn metadata_roundtrip() {
+    let metadata = EnvelopeMetadata::new()
+        .producer("billing")
+        .created_at_ms(1_700_000_000_000)
+        .tenant_id("acme")
+        .trace_id("4bf92f3577b34da6a3ce929d0e0e4736")
+        .entry(42, vec![0xde, 0xad]);
+    let codec = BincodeCodec::new(1, 1024);
+    let registry = StaticRegistry::new(&[], &[]);
+    let encoded = encode_to_envelope_with_metadata(
+        &7u32,
+        &codec,
+        key(),
+        &[],
+        &metadata,
+        &registry,
+        &EncodeLimits::new(1024),
+    )
+    .expect("encode");
+
+    let view = match try_parse(&encoded).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+
+    assert_eq!(view.version, 3);
+    assert_eq!(view.key, key());
+    assert_eq!(view.metadata.producer(), Some("billing"));
+    assert_eq!(view.metadata.created_at_ms(), Some(1_700_000_000_000));
+    assert_eq!(view.metadata.tenant_id(), Some("acme"));
+    assert_eq!(view.metadata.get(42), Some(&[0xde, 0xad][..]));
+    assert_eq!(
+        view.metadata.to_json(),
+        json!({
+            "producer": "billing",
+            "created_at_ms": 1_700_000_000_000i64,
+            "tenant_id": "acme",
+            "trace_id": "4bf92f3577b34da6a3ce929d0e0e4736",
+            "tag_42": "dead",
+        })
+    );
+}
+
+#[test]
+fn empty_metadata_builds_v2() {
+    let bytes = build_envelope_with_metadata(key(), 1, &[], &EnvelopeMetadata::new(), b"payload")
+        .expect("build");
+
+    let view = match try_parse(&bytes).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+
+    assert_eq!(view.version, 2);
+    assert!(view.metadata.is_empty());
+    assert_eq!(view.payload, b"payload");
+}
+
+#[test]
+fn metadata_too_large_is_rejected() {
+    let metadata = EnvelopeMetadata::new().entry(9, vec![0u8; MAX_METADATA_BYTES]);
+    let err = build_envelope_with_metadata(key(), 1, &[], &metadata, b"payload")
+        .expect_err("expected error");
+    match err {
+        DecodeError::LimitExceeded { context, .. } => assert_eq!(context, "metadata_bytes"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn metadata_entry_out_of_bounds() {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"PGDEBYTE");
+    bytes.push(3);
+    bytes.extend_from_slice(key().type_id.as_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(&4u16.to_le_bytes());
+    bytes.push(1);
+    bytes.extend_from_slice(&9u16.to_le_bytes());
+    bytes.push(b'x');
+
+    let err = try_parse(&bytes).expect_err("expected error");
+    match err {
+        DecodeError::BadEnvelope(msg) => assert_eq!(msg, "metadata value out of bounds"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
@@ -0,0 +1,230 @@
+#![cfg(feature = "protobuf")]
+
+use pg_debyte_core::envelope::{build_envelope, try_parse, try_parse_confluent, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::protobuf::ProtobufDecoderEntry;
+use pg_debyte_core::registry::{DecoderEntry, Registry, StaticRegistry};
+use pg_debyte_core::types::{DecodeLimits, TypeKey};
+use prost_reflect::prost::Message;
+use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+use prost_reflect::prost_types::{
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+};
+use prost_reflect::{DynamicMessage, Value};
+use serde_json::json;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+const KEY: TypeKey = TypeKey {
+    type_id: Uuid::from_bytes([0x66; 16]),
+    schema_version: 1,
+};
+const PROTOBUF_CODEC_ID: u16 = 4;
+
+fn field(name: &str, number: i32, ty: Type, label: Label) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        r#type: Some(ty as i32),
+        label: Some(label as i32),
+        json_name: None,
+        ..Default::default()
+    }
+}
+
+fn descriptor_set() -> &'static [u8] {
+    static BYTES: OnceLock<Vec<u8>> = OnceLock::new();
+    BYTES.get_or_init(|| {
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("demo.proto".to_string()),
+                package: Some("demo".to_string()),
+                syntax: Some("proto3".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Reading".to_string()),
+                    field: vec![
+                        field("sensor_id", 1, Type::String, Label::Optional),
+                        field("ticks", 2, Type::Int64, Label::Optional),
+                        field("raw", 3, Type::Bytes, Label::Optional),
+                        field("samples", 4, Type::Int32, Label::Repeated),
+                        field("active", 5, Type::Bool, Label::Optional),
+                    ],
+                    nested_type: vec![DescriptorProto {
+                        name: Some("Calibration".to_string()),
+                        field: vec![field("offset", 1, Type::Int32, Label::Optional)],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    })
+}
+
+fn reading_payload(entry: &ProtobufDecoderEntry) -> Vec<u8> {
+    let mut message = DynamicMessage::new(entry.descriptor().expect("descriptor").clone());
+    message.set_field_by_name("sensor_id", Value::String("t1".to_string()));
+    message.set_field_by_name("ticks", Value::I64(42));
+    message.set_field_by_name("raw", Value::Bytes(vec![1, 2].into()));
+    message.set_field_by_name("samples", Value::List(vec![Value::I32(1), Value::I32(2)]));
+    message.encode_to_vec()
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(1024, 1024, 1024)
+}
+
+#[test]
+fn protobuf_entry_produces_canonical_json() {
+    let entry = ProtobufDecoderEntry::from_descriptor_set(
+        KEY,
+        PROTOBUF_CODEC_ID,
+        descriptor_set(),
+        "demo.Reading",
+        &[],
+    )
+    .expect("descriptor");
+    let value = entry
+        .decode_payload(&reading_payload(&entry), &limits())
+        .expect("decode");
+
+    assert_eq!(
+        value,
+        json!({
+            "sensorId": "t1",
+            "ticks": "42",
+            "raw": "AQI=",
+            "samples": [1, 2],
+        })
+    );
+}
+
+#[test]
+fn protobuf_entry_loads_embedded_descriptor_lazily() {
+    static ENTRY: OnceLock<ProtobufDecoderEntry> = OnceLock::new();
+    let entry = ENTRY.get_or_init(|| {
+        ProtobufDecoderEntry::new(
+            KEY,
+            PROTOBUF_CODEC_ID,
+            descriptor_set(),
+            "demo.Reading",
+            &[],
+        )
+    });
+    let decoders: &'static [&'static dyn DecoderEntry] = Box::leak(Box::new([entry as _]));
+    let registry = StaticRegistry::new(decoders, &[]);
+
+    let payload = reading_payload(entry);
+    let envelope = build_envelope(KEY, PROTOBUF_CODEC_ID, &[], &payload).expect("build");
+    let found = registry.lookup_decoder(KEY).expect("registered");
+    assert_eq!(found.codec_id(), PROTOBUF_CODEC_ID);
+    let view = match try_parse(&envelope).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+    let value = found
+        .decode_payload(view.payload, &limits())
+        .expect("decode");
+    assert_eq!(value["sensorId"], "t1");
+}
+
+#[test]
+fn protobuf_entry_reports_unknown_message() {
+    let entry = ProtobufDecoderEntry::new(
+        KEY,
+        PROTOBUF_CODEC_ID,
+        descriptor_set(),
+        "demo.Missing",
+        &[],
+    );
+    match entry.decode_payload(&[], &limits()) {
+        Err(DecodeError::Protobuf(message)) => {
+            assert_eq!(message, "message demo.Missing not found in descriptor set")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn protobuf_entry_rejects_malformed_payload() {
+    let entry = ProtobufDecoderEntry::new(
+        KEY,
+        PROTOBUF_CODEC_ID,
+        descriptor_set(),
+        "demo.Reading",
+        &[],
+    );
+    assert!(matches!(
+        entry.decode_payload(&[0x0a, 0x05, b'a'], &limits()),
+        Err(DecodeError::Protobuf(_))
+    ));
+}
+
+#[test]
+fn protobuf_entry_respects_input_limit() {
+    let entry = ProtobufDecoderEntry::new(
+        KEY,
+        PROTOBUF_CODEC_ID,
+        descriptor_set(),
+        "demo.Reading",
+        &[],
+    );
+    match entry.decode_payload(&[0u8; 16], &DecodeLimits::new(1024, 8, 1024)) {
+        Err(DecodeError::LimitExceeded { context, .. }) => {
+            assert_eq!(context, "codec_input_bytes")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn protobuf_entry_decodes_confluent_records() {
+    let entry = ProtobufDecoderEntry::from_descriptor_set(
+        KEY,
+        PROTOBUF_CODEC_ID,
+        descriptor_set(),
+        "demo.Reading",
+        &[],
+    )
+    .expect("descriptor");
+    let payload = reading_payload(&entry);
+    // The first message is written as a lone 0, or as the explicit path [0].
+    for indexes in [&[0x00][..], &[0x02, 0x00]] {
+        let record = [&[0, 0, 0, 0, 42][..], indexes, &payload].concat();
+        let framed = try_parse_confluent(&record).expect("confluent");
+        assert_eq!(framed.schema_id, 42);
+        let stripped = entry
+            .strip_confluent_framing(framed.payload)
+            .expect("indexes");
+        let value = entry.decode_payload(stripped, &limits()).expect("decode");
+        assert_eq!(value["sensorId"], "t1");
+    }
+
+    let nested = ProtobufDecoderEntry::from_descriptor_set(
+        KEY,
+        PROTOBUF_CODEC_ID,
+        descriptor_set(),
+        "demo.Reading.Calibration",
+        &[],
+    )
+    .expect("descriptor");
+    let record = [0, 0, 0, 0, 42, 0x04, 0x00, 0x00, 0x08, 0x05];
+    let framed = try_parse_confluent(&record).expect("confluent");
+    let stripped = nested
+        .strip_confluent_framing(framed.payload)
+        .expect("indexes");
+    assert_eq!(
+        nested.decode_payload(stripped, &limits()).expect("decode"),
+        json!({"offset": 5})
+    );
+
+    // Indexes naming another message are rejected instead of decoded as payload.
+    for indexes in [&[0x02, 0x02][..], &[0x04, 0x00, 0x00], &[0x01]] {
+        assert!(matches!(
+            entry.strip_confluent_framing(indexes),
+            Err(DecodeError::Protobuf(_))
+        ));
+    }
+}
@@ -12,4 +12,18 @@ fn registry_lookup_missing_entries() {
 
     assert!(registry.lookup_decoder(key).is_none());
     assert!(registry.lookup_action(42).is_none());
+    assert!(registry.lookup_schema_id(42).is_none());
+}
+
+#[test]
+fn registry_maps_schema_ids() {
+    const KEY: TypeKey = TypeKey {
+        type_id: Uuid::from_bytes([7; 16]),
+        schema_version: 2,
+    };
+    static REGISTRY: StaticRegistry =
+        StaticRegistry::new(&[], &[]).with_schema_ids(&[(100, KEY), (101, KEY)]);
+
+    assert_eq!(REGISTRY.lookup_schema_id(101), Some(KEY));
+    assert!(REGISTRY.lookup_schema_id(102).is_none());
 }
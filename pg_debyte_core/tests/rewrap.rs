@@ -0,0 +1,140 @@
+use pg_debyte_core::action::{ActionSpec, ByteAction, ZstdAction};
+use pg_debyte_core::codec::{BincodeCodec, Codec};
+use pg_debyte_core::compact::try_parse_compact;
+use pg_debyte_core::encode::{
+    encode_to_compact_envelope, encode_to_envelope_with_metadata, rewrap_envelope,
+};
+use pg_debyte_core::envelope::{try_parse, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::metadata::EnvelopeMetadata;
+use pg_debyte_core::registry::StaticRegistry;
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Demo {
+    id: u32,
+    name: String,
+}
+
+const TYPE_ID: Uuid = Uuid::from_bytes([0x21; 16]);
+
+static ZSTD: ZstdAction = ZstdAction::new(1);
+static ACTIONS: [&'static dyn ByteAction; 1] = [&ZSTD];
+static REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &ACTIONS).with_type_aliases(&[(5, TYPE_ID)]);
+
+fn demo() -> Demo {
+    Demo {
+        id: 11,
+        name: "rewrap ".repeat(32),
+    }
+}
+
+fn key() -> TypeKey {
+    TypeKey {
+        type_id: TYPE_ID,
+        schema_version: 4,
+    }
+}
+
+#[test]
+fn rewrap_plain_to_zstd_keeps_header() {
+    let codec = BincodeCodec::new(3, 4096);
+    let encode_limits = EncodeLimits::new(4096);
+    let decode_limits = DecodeLimits::new(4096, 4096, 4096);
+    let metadata = EnvelopeMetadata::new().producer("rewrap-test");
+    let plain = encode_to_envelope_with_metadata(
+        &demo(),
+        &codec,
+        key(),
+        &[],
+        &metadata,
+        &REGISTRY,
+        &encode_limits,
+    )
+    .expect("encode");
+
+    let actions = vec![ActionSpec::new(1, 0, vec![19])];
+    let rewrapped = rewrap_envelope(&plain, &actions, &REGISTRY, &decode_limits, &encode_limits)
+        .expect("rewrap");
+    assert!(rewrapped.len() < plain.len());
+
+    let view = match try_parse(&rewrapped).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    };
+    assert_eq!(view.key, key());
+    assert_eq!(view.codec_id, 3);
+    assert_eq!(view.metadata.producer(), Some("rewrap-test"));
+    let action = view.actions.get(0).expect("action");
+    assert_eq!((action.id, action.params), (1, &[19][..]));
+
+    let payload = ZSTD
+        .decode(view.payload, &decode_limits, action.params)
+        .expect("decompress");
+    let decoded: Demo = codec.decode(&payload, &decode_limits).expect("decode");
+    assert_eq!(decoded, demo());
+
+    let unwrapped = rewrap_envelope(&rewrapped, &[], &REGISTRY, &decode_limits, &encode_limits)
+        .expect("rewrap back");
+    assert_eq!(unwrapped, plain);
+}
+
+#[test]
+fn rewrap_compact_envelope() {
+    let codec = BincodeCodec::new(3, 4096);
+    let encode_limits = EncodeLimits::new(4096);
+    let decode_limits = DecodeLimits::new(4096, 4096, 4096);
+    let actions = vec![ActionSpec::new(1, 0, vec![3])];
+    let compact =
+        encode_to_compact_envelope(&demo(), &codec, key(), &actions, &REGISTRY, &encode_limits)
+            .expect("encode");
+
+    let rewrapped =
+        rewrap_envelope(&compact, &[], &REGISTRY, &decode_limits, &encode_limits).expect("rewrap");
+    let view = try_parse_compact(&rewrapped)
+        .expect("parse")
+        .expect("compact envelope");
+    assert_eq!(view.resolve_key(&REGISTRY).expect("resolve"), key());
+    assert!(view.actions.is_empty());
+    let decoded: Demo = codec.decode(view.payload, &decode_limits).expect("decode");
+    assert_eq!(decoded, demo());
+}
+
+#[test]
+fn rewrap_rejects_unknown_action() {
+    let codec = BincodeCodec::new(3, 4096);
+    let encode_limits = EncodeLimits::new(4096);
+    let decode_limits = DecodeLimits::new(4096, 4096, 4096);
+    let plain = encode_to_envelope_with_metadata(
+        &demo(),
+        &codec,
+        key(),
+        &[],
+        &EnvelopeMetadata::new(),
+        &REGISTRY,
+        &encode_limits,
+    )
+    .expect("encode");
+
+    let actions = vec![ActionSpec::new(77, 0, Vec::new())];
+    let err = rewrap_envelope(&plain, &actions, &REGISTRY, &decode_limits, &encode_limits)
+        .expect_err("expected error");
+    match err {
+        DecodeError::UnknownAction(id) => assert_eq!(id, 77),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn rewrap_requires_envelope() {
+    let limits = DecodeLimits::new(64, 64, 64);
+    let err = rewrap_envelope(b"raw", &[], &REGISTRY, &limits, &EncodeLimits::new(64))
+        .expect_err("expected error");
+    match err {
+        DecodeError::BadEnvelope(msg) => assert_eq!(msg, "no envelope"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
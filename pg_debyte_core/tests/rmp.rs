@@ -0,0 +1,122 @@
+use pg_debyte_core::codec::{Codec, RmpCodec, RmpStructLayout};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::{DecoderEntry, TypedDecoderEntry};
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Demo {
+    id: u32,
+    name: String,
+}
+
+fn demo() -> Demo {
+    Demo {
+        id: 7,
+        name: "demo".to_string(),
+    }
+}
+
+#[test]
+fn rmp_roundtrip_in_both_layouts() {
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    let encode_limits = EncodeLimits::new(1024);
+    for layout in [RmpStructLayout::Map, RmpStructLayout::Array] {
+        let codec = RmpCodec::new(2, 1024).with_layout(layout);
+        let bytes = codec.encode(&demo(), &encode_limits).expect("encode");
+        let decoded: Demo = codec.decode(&bytes, &limits).expect("decode");
+        assert_eq!(decoded, demo());
+    }
+}
+
+#[test]
+fn rmp_layouts_differ_on_the_wire() {
+    let encode_limits = EncodeLimits::new(1024);
+    let map = RmpCodec::new(2, 1024)
+        .encode(&demo(), &encode_limits)
+        .expect("map");
+    let array = RmpCodec::new(2, 1024)
+        .with_layout(RmpStructLayout::Array)
+        .encode(&demo(), &encode_limits)
+        .expect("array");
+
+    assert_eq!(map[0], 0x82);
+    assert_eq!(array[0], 0x92);
+    assert_eq!(map, rmp_serde::to_vec_named(&demo()).expect("named"));
+    assert_eq!(array, rmp_serde::to_vec(&demo()).expect("positional"));
+}
+
+#[test]
+fn rmp_decode_accepts_either_layout() {
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    let codec = RmpCodec::new(2, 1024);
+    let from_array: Demo = codec
+        .decode(&rmp_serde::to_vec(&demo()).expect("encode"), &limits)
+        .expect("decode");
+    assert_eq!(from_array, demo());
+}
+
+#[test]
+fn rmp_decode_respects_input_limit() {
+    let bytes = rmp_serde::to_vec_named(&demo()).expect("encode");
+    let codec = RmpCodec::new(2, 4);
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    match codec.decode::<Demo>(&bytes, &limits) {
+        Err(DecodeError::LimitExceeded { context, .. }) => {
+            assert_eq!(context, "codec_input_bytes")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn rmp_encode_respects_output_limit() {
+    let codec = RmpCodec::new(2, 1024);
+    match codec.encode(&demo(), &EncodeLimits::new(4)) {
+        Err(DecodeError::LimitExceeded { context, limit, .. }) => {
+            assert_eq!(context, "codec_output_bytes");
+            assert_eq!(limit, 4);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn rmp_decode_rejects_trailing_and_truncated_bytes() {
+    let codec = RmpCodec::new(2, 1024);
+    let limits = DecodeLimits::new(1024, 1024, 1024);
+    let mut bytes = rmp_serde::to_vec_named(&demo()).expect("encode");
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(matches!(
+        codec.decode::<Demo>(truncated, &limits),
+        Err(DecodeError::MessagePack(_))
+    ));
+
+    bytes.push(0xc0);
+    assert!(matches!(
+        codec.decode::<Demo>(&bytes, &limits),
+        Err(DecodeError::MessagePack(_))
+    ));
+}
+
+#[test]
+fn rmp_decoder_entry_produces_json() {
+    static ENTRY: TypedDecoderEntry<Demo, RmpCodec> = TypedDecoderEntry::new(
+        TypeKey {
+            type_id: Uuid::from_bytes([0x44; 16]),
+            schema_version: 1,
+        },
+        RmpCodec::new(2, 1024),
+        &[],
+    );
+    let bytes = rmp_serde::to_vec_named(&demo()).expect("encode");
+    let value = ENTRY
+        .decode_payload(&bytes, &DecodeLimits::new(1024, 1024, 1024))
+        .expect("decode");
+
+    assert_eq!(ENTRY.codec_id(), 2);
+    assert_eq!(value, json!({"id": 7, "name": "demo"}));
+}
@@ -0,0 +1,113 @@
+use pg_debyte_core::codec::{CborCodec, Codec, RmpCodec};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::registry::{DecoderEntry, Registry, StaticRegistry};
+use pg_debyte_core::schemaless::{SchemalessDecoderEntry, SchemalessFormat};
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde_json::json;
+use uuid::Uuid;
+
+const KEY: TypeKey = TypeKey {
+    type_id: Uuid::from_bytes([3; 16]),
+    schema_version: 1,
+};
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(1024, 1024, 1024)
+}
+
+#[test]
+fn schemaless_decodes_messagepack() {
+    let value = json!({"id": 1, "tags": ["a", "b"], "nested": {"ok": true, "none": null}});
+    let payload = RmpCodec::new(2, 1024)
+        .encode(&value, &EncodeLimits::new(1024))
+        .expect("encode");
+
+    let entry = SchemalessDecoderEntry::new(KEY, 2, SchemalessFormat::MessagePack, &[]);
+    assert_eq!(
+        entry.decode_payload(&payload, &limits()).expect("decode"),
+        value
+    );
+
+    // bin 8 with two bytes
+    let decoded = entry
+        .decode_payload(&[0xc4, 0x02, 0xde, 0xad], &limits())
+        .expect("decode");
+    assert_eq!(decoded, json!("\\xdead"));
+}
+
+#[test]
+fn schemaless_decodes_cbor() {
+    let value = json!({"id": 1, "items": [1.5, "x", false]});
+    let payload = CborCodec::new(3, 1024)
+        .encode(&value, &EncodeLimits::new(1024))
+        .expect("encode");
+
+    let entry = SchemalessDecoderEntry::new(KEY, 3, SchemalessFormat::Cbor, &[]);
+    assert_eq!(
+        entry.decode_payload(&payload, &limits()).expect("decode"),
+        value
+    );
+
+    // {1: "one"}
+    let decoded = entry
+        .decode_payload(&[0xa1, 0x01, 0x63, b'o', b'n', b'e'], &limits())
+        .expect("decode");
+    assert_eq!(decoded, json!({"1": "one"}));
+
+    let nested = [0x81; 8];
+    let shallow = entry.with_max_depth(4);
+    match shallow.decode_payload(&nested, &limits()) {
+        Err(DecodeError::Cbor(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn schemaless_decodes_json() {
+    let entry = SchemalessDecoderEntry::new(KEY, 4, SchemalessFormat::Json, &[]);
+    let decoded = entry
+        .decode_payload(br#"{"id": 1, "name": "demo"}"#, &limits())
+        .expect("decode");
+    assert_eq!(decoded, json!({"id": 1, "name": "demo"}));
+
+    match entry.decode_payload(br#"{"id": 1} 2"#, &limits()) {
+        Err(DecodeError::Json(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn schemaless_respects_limits() {
+    for format in [
+        SchemalessFormat::MessagePack,
+        SchemalessFormat::Cbor,
+        SchemalessFormat::Json,
+    ] {
+        let entry = SchemalessDecoderEntry::new(KEY, 2, format, &[]).with_byte_limit(4);
+        match entry.decode_payload(b"\"longer\"", &limits()) {
+            Err(DecodeError::LimitExceeded { context, limit, .. }) => {
+                assert_eq!(context, "codec_input_bytes");
+                assert_eq!(limit, 4);
+            }
+            other => panic!("unexpected result for {format:?}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn registry_matches_fallback_decoders_by_codec_id() {
+    static MSGPACK: SchemalessDecoderEntry =
+        SchemalessDecoderEntry::fallback(2, SchemalessFormat::MessagePack);
+    static CBOR: SchemalessDecoderEntry =
+        SchemalessDecoderEntry::fallback(3, SchemalessFormat::Cbor);
+    static REGISTRY: StaticRegistry =
+        StaticRegistry::new(&[], &[]).with_fallback_decoders(&[&MSGPACK, &CBOR]);
+
+    assert!(REGISTRY.lookup_decoder(MSGPACK.key()).is_none());
+    let entry = REGISTRY.lookup_fallback_decoder(3).expect("fallback");
+    assert_eq!(entry.codec_id(), 3);
+    assert!(REGISTRY.lookup_fallback_decoder(1).is_none());
+    assert!(StaticRegistry::new(&[], &[])
+        .lookup_fallback_decoder(2)
+        .is_none());
+}
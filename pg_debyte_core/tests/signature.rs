@@ -0,0 +1,372 @@
+#![cfg(feature = "signature")]
+
+use ed25519_dalek::SigningKey;
+use hmac::{Hmac, Mac};
+use pg_debyte_core::action::{
+    decode_actions, decode_actions_in, ensure_required_actions, ActionContext, ActionSpec,
+    ByteAction,
+};
+use pg_debyte_core::codec::{BincodeCodec, Codec};
+use pg_debyte_core::encode::encode_to_envelope;
+use pg_debyte_core::envelope::{
+    try_parse, try_parse_unverified, EnvelopeBuilder, EnvelopeView, ParsedEnvelope,
+};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::keys::{KeyBytes, KeyProvider};
+use pg_debyte_core::registry::{Registry, StaticRegistry, TypedDecoderEntry};
+use pg_debyte_core::signature::{
+    SignatureAction, SignatureAlgorithm, ED25519_SIGNATURE_LEN, ENVELOPE_BINDING_LEN,
+    HMAC_SHA256_TAG_LEN,
+};
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Message {
+    id: u32,
+    body: String,
+}
+
+const HMAC_SECRET: &[u8] = b"shared hmac secret";
+const ED25519_SEED: [u8; 32] = [0x07; 32];
+
+/// Holds the HMAC secret (1) and the Ed25519 keypair (2).
+struct SigningKeys;
+
+impl KeyProvider for SigningKeys {
+    fn key(&self, key_id: u32) -> Result<Option<KeyBytes>, DecodeError> {
+        Ok(match key_id {
+            1 => Some(KeyBytes::new(HMAC_SECRET.to_vec())),
+            2 => Some(KeyBytes::new(
+                SigningKey::from_bytes(&ED25519_SEED)
+                    .to_keypair_bytes()
+                    .to_vec(),
+            )),
+            _ => None,
+        })
+    }
+}
+
+/// Holds only the Ed25519 public key (2).
+struct VerifyingKeys;
+
+impl KeyProvider for VerifyingKeys {
+    fn key(&self, key_id: u32) -> Result<Option<KeyBytes>, DecodeError> {
+        Ok((key_id == 2).then(|| {
+            KeyBytes::new(
+                SigningKey::from_bytes(&ED25519_SEED)
+                    .verifying_key()
+                    .to_bytes()
+                    .to_vec(),
+            )
+        }))
+    }
+}
+
+static HMAC: SignatureAction = SignatureAction::new(30, SignatureAlgorithm::HmacSha256);
+static ED25519: SignatureAction = SignatureAction::new(31, SignatureAlgorithm::Ed25519);
+static SIGNING_KEYS: SigningKeys = SigningKeys;
+static VERIFYING_KEYS: VerifyingKeys = VerifyingKeys;
+static REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &[&HMAC, &ED25519]).with_key_provider(&SIGNING_KEYS);
+static SIGNED_V1: TypedDecoderEntry<Message, BincodeCodec> =
+    TypedDecoderEntry::new(key(), BincodeCodec::new(1, 4096), &[]).with_required_actions(&[30]);
+static SIGNED_V2: TypedDecoderEntry<Message, BincodeCodec> = TypedDecoderEntry::new(
+    TypeKey {
+        type_id: Uuid::from_bytes([0x51; 16]),
+        schema_version: 2,
+    },
+    BincodeCodec::new(1, 4096),
+    &[],
+)
+.with_required_actions(&[30]);
+static POLICY_REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[&SIGNED_V1, &SIGNED_V2], &[&HMAC]).with_key_provider(&SIGNING_KEYS);
+static VERIFY_REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &[&HMAC, &ED25519]).with_key_provider(&VERIFYING_KEYS);
+static PINNED_HMAC: SignatureAction =
+    SignatureAction::new(30, SignatureAlgorithm::HmacSha256).with_key_ids(&[1]);
+static PINNED_REGISTRY: StaticRegistry =
+    StaticRegistry::new(&[], &[&PINNED_HMAC]).with_key_provider(&SIGNING_KEYS);
+
+fn message() -> Message {
+    Message {
+        id: 3,
+        body: "signed".to_string(),
+    }
+}
+
+const fn key() -> TypeKey {
+    TypeKey {
+        type_id: Uuid::from_bytes([0x51; 16]),
+        schema_version: 1,
+    }
+}
+
+fn limits() -> DecodeLimits {
+    DecodeLimits::new(4096, 4096, 4096)
+}
+
+fn sign(registry: &StaticRegistry, action_id: u16, key_id: u32) -> Result<Vec<u8>, DecodeError> {
+    let actions = [ActionSpec::new(action_id, 0, key_id.to_le_bytes().to_vec())];
+    encode_to_envelope(
+        &message(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &actions,
+        registry,
+        &EncodeLimits::new(4096),
+    )
+}
+
+fn view(envelope: &[u8]) -> EnvelopeView<'_> {
+    match try_parse(envelope).expect("parse") {
+        ParsedEnvelope::Envelope(view) => view,
+        ParsedEnvelope::None => panic!("expected envelope"),
+    }
+}
+
+fn verify(registry: &StaticRegistry, view: &EnvelopeView<'_>) -> Result<Message, DecodeError> {
+    let context = ActionContext::new(registry)
+        .with_key(view.key)
+        .with_codec_id(view.codec_id);
+    let payload = decode_actions_in(&context, view.actions, view.payload, &limits())?;
+    BincodeCodec::new(1, 4096).decode(&payload, &limits())
+}
+
+/// Decodes through the registered entry the way `bytea_to_json_auto` does.
+fn decode_entry(view: &EnvelopeView<'_>) -> Result<serde_json::Value, DecodeError> {
+    let entry = POLICY_REGISTRY
+        .lookup_decoder(view.key)
+        .ok_or(DecodeError::UnknownType(view.key))?;
+    if view.codec_id != entry.codec_id() {
+        return Err(DecodeError::UnknownCodec(view.codec_id));
+    }
+    ensure_required_actions(
+        entry.required_actions(),
+        view.actions.iter().map(|action| action.id),
+    )?;
+    let context = ActionContext::new(&POLICY_REGISTRY)
+        .with_key(view.key)
+        .with_codec_id(view.codec_id);
+    let payload = decode_actions_in(&context, view.actions, view.payload, &limits())?;
+    entry.decode_payload(&payload, &limits())
+}
+
+fn relabel(view: &EnvelopeView<'_>, key: TypeKey, codec_id: u16, strip: bool) -> Vec<u8> {
+    let actions: Vec<ActionSpec> = if strip {
+        Vec::new()
+    } else {
+        view.actions.iter().map(ActionSpec::from).collect()
+    };
+    let payload = if strip {
+        &view.payload[..view.payload.len() - HMAC_SHA256_TAG_LEN]
+    } else {
+        view.payload
+    };
+    EnvelopeBuilder::new(key, codec_id)
+        .actions(&actions)
+        .build(payload)
+        .expect("build")
+}
+
+#[test]
+fn signature_roundtrip_appends_tag() {
+    for (action_id, key_id, tag_len) in
+        [(30, 1, HMAC_SHA256_TAG_LEN), (31, 2, ED25519_SIGNATURE_LEN)]
+    {
+        let envelope = sign(&REGISTRY, action_id, key_id).expect("sign");
+        let view = view(&envelope);
+        let plain = BincodeCodec::new(1, 4096)
+            .encode(&message(), &EncodeLimits::new(4096))
+            .expect("encode");
+        assert_eq!(view.payload.len(), plain.len() + tag_len);
+        assert_eq!(&view.payload[..plain.len()], plain.as_slice());
+
+        assert_eq!(verify(&REGISTRY, &view).expect("verify"), message());
+        // The tag binds the envelope, so there is nothing to check it against without one.
+        match decode_actions(&REGISTRY, view.actions, view.payload, &limits()) {
+            Err(DecodeError::InvalidKey(_)) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn hmac_tag_matches_external_signer() {
+    let envelope = sign(&REGISTRY, 30, 1).expect("sign");
+    let view = view(&envelope);
+    let (message, tag) = view
+        .payload
+        .split_at(view.payload.len() - HMAC_SHA256_TAG_LEN);
+    let mut binding = Vec::with_capacity(ENVELOPE_BINDING_LEN);
+    binding.extend_from_slice(key().type_id.as_bytes());
+    binding.extend_from_slice(&key().schema_version.to_le_bytes());
+    binding.extend_from_slice(&1u16.to_le_bytes());
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(HMAC_SECRET).expect("mac");
+    mac.update(&binding);
+    mac.update(message);
+    mac.verify_slice(tag).expect("external verify");
+}
+
+#[test]
+fn ed25519_verifies_with_public_key_only() {
+    let envelope = sign(&REGISTRY, 31, 2).expect("sign");
+    assert_eq!(
+        verify(&VERIFY_REGISTRY, &view(&envelope)).expect("verify"),
+        message()
+    );
+
+    match sign(&VERIFY_REGISTRY, 31, 2) {
+        Err(DecodeError::InvalidKey(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn signature_rejects_tampering() {
+    for (action_id, key_id) in [(30, 1), (31, 2)] {
+        let mut envelope = sign(&REGISTRY, action_id, key_id).expect("sign");
+        let last = envelope.len() - 1;
+        envelope[last] ^= 0x01;
+        let view = match try_parse_unverified(&envelope).expect("parse") {
+            ParsedEnvelope::Envelope(view) => view,
+            ParsedEnvelope::None => panic!("expected envelope"),
+        };
+        match verify(&REGISTRY, &view) {
+            Err(DecodeError::SignatureInvalid { key_id: id }) => assert_eq!(id, key_id),
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        // Truncated below the tag length.
+        match decode_actions(&REGISTRY, view.actions, &view.payload[..8], &limits()) {
+            Err(DecodeError::SignatureInvalid { .. }) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn signature_rejects_wrong_and_unknown_keys() {
+    let envelope = sign(&REGISTRY, 30, 1).expect("sign");
+    let signed = view(&envelope);
+
+    // The Ed25519 keypair is a valid HMAC key, it just does not match.
+    let params = 2u32.to_le_bytes();
+    let actions = [ActionSpec::new(30, 0, params.to_vec())];
+    let relabeled = EnvelopeBuilder::new(signed.key, signed.codec_id)
+        .actions(&actions)
+        .build(signed.payload)
+        .expect("build");
+    match verify(&REGISTRY, &view(&relabeled)) {
+        Err(DecodeError::SignatureInvalid { key_id: 2 }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    match verify(&VERIFY_REGISTRY, &signed) {
+        Err(DecodeError::UnknownKey(1)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    match sign(&REGISTRY, 30, 9) {
+        Err(DecodeError::UnknownKey(9)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn signature_rejects_keys_outside_the_allowed_ids() {
+    // Without pinning, anyone who knows the Ed25519 public key can HMAC with it.
+    let forged = sign(&VERIFY_REGISTRY, 30, 2).expect("sign");
+    assert_eq!(
+        verify(&VERIFY_REGISTRY, &view(&forged)).expect("verify"),
+        message()
+    );
+
+    let forged = sign(&REGISTRY, 30, 2).expect("sign");
+    match verify(&PINNED_REGISTRY, &view(&forged)) {
+        Err(DecodeError::SignatureInvalid { key_id: 2 }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let signed = sign(&REGISTRY, 30, 1).expect("sign");
+    assert_eq!(
+        verify(&PINNED_REGISTRY, &view(&signed)).expect("verify"),
+        message()
+    );
+
+    match sign(&PINNED_REGISTRY, 30, 2) {
+        Err(DecodeError::InvalidKey(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn signature_validates_params_and_limits() {
+    let actions = [ActionSpec::new(30, 0, vec![1, 0])];
+    match encode_to_envelope(
+        &message(),
+        &BincodeCodec::new(1, 4096),
+        key(),
+        &actions,
+        &REGISTRY,
+        &EncodeLimits::new(4096),
+    ) {
+        Err(DecodeError::InvalidActionParams { id: 30, .. }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let envelope = sign(&REGISTRY, 30, 1).expect("sign");
+    let view = view(&envelope);
+    match decode_actions(
+        &REGISTRY,
+        view.actions,
+        view.payload,
+        &DecodeLimits::new(4096, 4, 4096),
+    ) {
+        Err(DecodeError::LimitExceeded {
+            context: "action_output_bytes",
+            ..
+        }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    match HMAC.decode(view.payload, &limits(), &1u32.to_le_bytes()) {
+        Err(DecodeError::InvalidKey(_)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn required_signature_cannot_be_stripped_or_relabelled() {
+    let envelope = sign(&POLICY_REGISTRY, 30, 1).expect("sign");
+    let signed = view(&envelope);
+    assert_eq!(
+        decode_entry(&signed).expect("decode"),
+        serde_json::json!({"id": 3, "body": "signed"})
+    );
+
+    let stripped = relabel(&signed, signed.key, signed.codec_id, true);
+    match decode_entry(&view(&stripped)) {
+        Err(DecodeError::MissingAction(30)) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let other_version = TypeKey {
+        schema_version: 2,
+        ..signed.key
+    };
+    let relabelled = relabel(&signed, other_version, signed.codec_id, false);
+    match decode_entry(&view(&relabelled)) {
+        Err(DecodeError::SignatureInvalid { key_id: 1 }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let other_codec = relabel(&signed, signed.key, 2, false);
+    match verify(&POLICY_REGISTRY, &view(&other_codec)) {
+        Err(DecodeError::SignatureInvalid { key_id: 1 }) => {}
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -0,0 +1,91 @@
+use pg_debyte_core::envelope::{build_envelope, try_parse, ParsedEnvelope};
+use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::stream::{append_frame, frames, write_frame, FrameReader};
+use pg_debyte_core::types::TypeKey;
+use uuid::Uuid;
+
+fn envelope(schema_version: u16, payload: &[u8]) -> Vec<u8> {
+    let key = TypeKey {
+        type_id: Uuid::from_bytes([0x31; 16]),
+        schema_version,
+    };
+    build_envelope(key, 1, &[], payload).expect("build")
+}
+
+fn stream() -> Vec<u8> {
+    let mut output = Vec::new();
+    append_frame(&mut output, &envelope(1, b"first")).expect("frame");
+    append_frame(&mut output, &envelope(2, b"")).expect("frame");
+    write_frame(&mut output, &envelope(3, b"third")).expect("frame");
+    output
+}
+
+#[test]
+fn frames_iterate_back_to_back_envelopes() {
+    let bytes = stream();
+    let parsed: Vec<(u16, Vec<u8>)> = frames(&bytes)
+        .map(
+            |frame| match try_parse(frame.expect("frame")).expect("parse") {
+                ParsedEnvelope::Envelope(view) => (view.key.schema_version, view.payload.to_vec()),
+                ParsedEnvelope::None => panic!("expected envelope"),
+            },
+        )
+        .collect();
+
+    assert_eq!(
+        parsed,
+        vec![
+            (1, b"first".to_vec()),
+            (2, Vec::new()),
+            (3, b"third".to_vec())
+        ]
+    );
+}
+
+#[test]
+fn frames_report_truncation_once() {
+    let bytes = stream();
+    let mut iter = frames(&bytes[..bytes.len() - 1]);
+    assert!(iter.next().expect("first").is_ok());
+    assert!(iter.next().expect("second").is_ok());
+    match iter.next().expect("third") {
+        Err(DecodeError::BadEnvelope(msg)) => assert_eq!(msg, "frame out of bounds"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn frame_reader_matches_slice_iterator() {
+    let bytes = stream();
+    let from_reader: Vec<Vec<u8>> = FrameReader::new(&bytes[..], 1024)
+        .collect::<Result<_, _>>()
+        .expect("read");
+    let from_slice: Vec<Vec<u8>> = frames(&bytes)
+        .map(|frame| frame.map(<[u8]>::to_vec))
+        .collect::<Result<_, _>>()
+        .expect("iterate");
+
+    assert_eq!(from_reader.len(), 3);
+    assert_eq!(from_reader, from_slice);
+}
+
+#[test]
+fn frame_reader_enforces_frame_limit() {
+    let bytes = stream();
+    let mut reader = FrameReader::new(&bytes[..], 8);
+    match reader.next().expect("frame") {
+        Err(DecodeError::LimitExceeded { context, .. }) => assert_eq!(context, "frame_bytes"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn frame_reader_rejects_partial_header() {
+    let mut reader = FrameReader::new(&[1u8, 0][..], 1024);
+    match reader.next().expect("frame") {
+        Err(DecodeError::BadEnvelope(msg)) => assert_eq!(msg, "frame header out of bounds"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
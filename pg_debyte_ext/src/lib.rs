@@ -1,7 +1,7 @@
 //! Example extension crate showing how to wire pg_debyte into PG17.
 
 use pgrx::prelude::*;
-use pgrx::JsonB;
+use pgrx::{JsonB, JsonString};
 
 #[cfg(any(test, feature = "pg_test"))]
 pub mod pg_test {
@@ -11,8 +11,13 @@ pub mod pg_test {
         vec![]
     }
 }
-use pg_debyte_core::{BincodeCodec, DecodeError, StaticRegistry, ZstdAction};
+use pg_debyte_core::{
+    AeadAction, AeadAlgorithm, BincodeCodec, DecodeError, EnvelopeEncryptionAction, RmpCodec,
+    SchemalessDecoderEntry, SchemalessFormat, SignatureAction, SignatureAlgorithm, StaticRegistry,
+    TypeKey as CoreTypeKey, TypedDecoderEntry, ZstdAction,
+};
 use pg_debyte_macros::{declare_decoder, declare_know_schema};
+use pg_debyte_pgrx::SpiDictionaryProvider;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid as CoreUuid;
 
@@ -28,9 +33,22 @@ const DEMO_TYPE_ID: CoreUuid = CoreUuid::from_bytes([0x11; 16]);
 const DEMO_SCHEMA_VERSION: u16 = 1;
 const DEMO_CODEC_ID: u16 = 1;
 const ZSTD_ACTION_ID: u16 = 1;
+const AEAD_ACTION_ID: u16 = 2;
+const ENVELOPE_ENCRYPTION_ACTION_ID: u16 = 3;
+const SIGNATURE_ACTION_ID: u16 = 4;
+const SIGNING_KEY_ID: u32 = 9;
+const DEMO_CONFLUENT_SCHEMA_ID: u32 = 42;
+const DEMO_TYPE_ALIAS: u32 = 1;
 
 const DEMO_CODEC: BincodeCodec = BincodeCodec::new(DEMO_CODEC_ID, 32 * 1024 * 1024);
 const ZSTD_ACTION: ZstdAction = ZstdAction::new(ZSTD_ACTION_ID);
+const AEAD_ACTION: AeadAction = AeadAction::new(AEAD_ACTION_ID, AeadAlgorithm::Aes256Gcm);
+const ENVELOPE_ENCRYPTION_ACTION: EnvelopeEncryptionAction =
+    EnvelopeEncryptionAction::new(ENVELOPE_ENCRYPTION_ACTION_ID, AeadAlgorithm::Aes256Gcm);
+// Only this key may sign: the provider also holds the AEAD keys.
+const SIGNATURE_ACTION: SignatureAction =
+    SignatureAction::new(SIGNATURE_ACTION_ID, SignatureAlgorithm::HmacSha256)
+        .with_key_ids(&[SIGNING_KEY_ID]);
 
 declare_decoder!(
     DEMO_DECODER,
@@ -63,7 +81,131 @@ declare_know_schema!(
     fn_name = bytea_to_json_demo_record_second
 );
 
-static REGISTRY: StaticRegistry = StaticRegistry::new(&[&DEMO_DECODER], &[&ZSTD_ACTION]);
+const DEMO_MSGPACK_TYPE_ID: CoreUuid = CoreUuid::from_bytes([0xb4; 16]);
+const DEMO_MSGPACK_CODEC_ID: u16 = 2;
+const DEMO_MSGPACK_CODEC: RmpCodec = RmpCodec::new(DEMO_MSGPACK_CODEC_ID, 32 * 1024 * 1024);
+
+declare_know_schema!(
+    DEMO_DECODER_MSGPACK,
+    ty = DemoRecordSecond,
+    type_id = DEMO_MSGPACK_TYPE_ID,
+    schema_version = 1,
+    codec = DEMO_MSGPACK_CODEC,
+    codec_ty = RmpCodec,
+    actions = [],
+    fn_name = bytea_to_json_demo_record_msgpack
+);
+
+const DEMO_SCHEMALESS_TYPE_ID: CoreUuid = CoreUuid::from_bytes([0xc5; 16]);
+const DEMO_CBOR_CODEC_ID: u16 = 3;
+
+static DEMO_DECODER_SCHEMALESS: SchemalessDecoderEntry = SchemalessDecoderEntry::new(
+    CoreTypeKey {
+        type_id: DEMO_SCHEMALESS_TYPE_ID,
+        schema_version: 1,
+    },
+    DEMO_MSGPACK_CODEC_ID,
+    SchemalessFormat::MessagePack,
+    &[],
+);
+static DEMO_CBOR_FALLBACK: SchemalessDecoderEntry =
+    SchemalessDecoderEntry::fallback(DEMO_CBOR_CODEC_ID, SchemalessFormat::Cbor);
+
+const DEMO_MIGRATING_TYPE_ID: CoreUuid = CoreUuid::from_bytes([0xe7; 16]);
+
+/// Written with bincode, also readable from MessagePack envelopes.
+static DEMO_DECODER_MIGRATING: TypedDecoderEntry<DemoRecordSecond, BincodeCodec> =
+    TypedDecoderEntry::new(
+        CoreTypeKey {
+            type_id: DEMO_MIGRATING_TYPE_ID,
+            schema_version: 1,
+        },
+        DEMO_CODEC,
+        &[],
+    )
+    .with_registered_codecs();
+
+const DEMO_SIGNED_TYPE_ID: CoreUuid = CoreUuid::from_bytes([0x55; 16]);
+
+/// Only accepted with a valid HMAC from `SIGNATURE_ACTION`.
+static DEMO_DECODER_SIGNED: TypedDecoderEntry<DemoRecord, BincodeCodec> = TypedDecoderEntry::new(
+    CoreTypeKey {
+        type_id: DEMO_SIGNED_TYPE_ID,
+        schema_version: 1,
+    },
+    DEMO_CODEC,
+    &[],
+)
+.with_required_actions(&[SIGNATURE_ACTION_ID]);
+
+extension_sql!(
+    r#"
+CREATE TABLE pg_debyte_dictionaries (
+    id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    dictionary bytea NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+-- Decoding looks dictionaries up with the caller's privileges.
+GRANT SELECT ON pg_debyte_dictionaries TO PUBLIC;
+
+-- Envelopes only record the id and backends cache what they load, so rows never change.
+CREATE FUNCTION pg_debyte_dictionaries_immutable() RETURNS trigger
+LANGUAGE plpgsql AS $$
+BEGIN
+    RAISE EXCEPTION 'pg_debyte_dictionaries rows are immutable; train a new dictionary instead'
+        USING ERRCODE = 'prohibited_sql_statement_attempted';
+END;
+$$;
+
+CREATE TRIGGER pg_debyte_dictionaries_immutable
+    BEFORE UPDATE OR DELETE ON pg_debyte_dictionaries
+    FOR EACH ROW EXECUTE FUNCTION pg_debyte_dictionaries_immutable();
+
+CREATE TRIGGER pg_debyte_dictionaries_no_truncate
+    BEFORE TRUNCATE ON pg_debyte_dictionaries
+    FOR EACH STATEMENT EXECUTE FUNCTION pg_debyte_dictionaries_immutable();
+
+-- Stored rows are user data that envelopes depend on, so pg_dump must include them.
+SELECT pg_catalog.pg_extension_config_dump('pg_debyte_dictionaries', '');
+SELECT pg_catalog.pg_extension_config_dump(
+    pg_get_serial_sequence('pg_debyte_dictionaries', 'id')::regclass,
+    ''
+);
+"#,
+    name = "pg_debyte_dictionaries",
+);
+
+/// Zstd dictionaries referenced as `[level, id (u32 LE)]` in `ZstdAction` params.
+static DICTIONARIES: SpiDictionaryProvider =
+    SpiDictionaryProvider::new("SELECT dictionary FROM pg_debyte_dictionaries WHERE id = $1");
+
+static REGISTRY: StaticRegistry = StaticRegistry::new(
+    &[
+        &DEMO_DECODER,
+        &DEMO_DECODER_SCHEMALESS,
+        &DEMO_DECODER_MIGRATING,
+        &DEMO_DECODER_SIGNED,
+    ],
+    &[
+        &ZSTD_ACTION,
+        &AEAD_ACTION,
+        &ENVELOPE_ENCRYPTION_ACTION,
+        &SIGNATURE_ACTION,
+    ],
+)
+.with_codecs(&[&DEMO_CODEC, &DEMO_MSGPACK_CODEC])
+.with_schema_ids(&[(
+    DEMO_CONFLUENT_SCHEMA_ID,
+    CoreTypeKey {
+        type_id: DEMO_TYPE_ID,
+        schema_version: DEMO_SCHEMA_VERSION,
+    },
+)])
+.with_type_aliases(&[(DEMO_TYPE_ALIAS, DEMO_TYPE_ID)])
+.with_fallback_decoders(&[&DEMO_CBOR_FALLBACK])
+.with_key_provider(&pg_debyte_pgrx::GUC_KEY_PROVIDER)
+.with_dictionary_provider(&DICTIONARIES);
 
 #[pg_guard]
 pub unsafe extern "C-unwind" fn _PG_init() {
@@ -72,33 +214,123 @@ pub unsafe extern "C-unwind" fn _PG_init() {
 }
 
 #[pg_extern]
-fn bytea_to_json_by_id(
+fn bytea_to_json_text_by_id(
     data: Vec<u8>,
     type_id: pgrx::Uuid,
     schema_version: i16,
-) -> Result<JsonB, DecodeError> {
+) -> Result<JsonString, DecodeError> {
     let limits = pg_debyte_pgrx::limits();
     let core_uuid = CoreUuid::from_bytes(*type_id.as_bytes());
-    let value = pg_debyte_pgrx::decode_by_id(&data, core_uuid, schema_version, &limits)?;
+    let json = pg_debyte_pgrx::decode_by_id_json(&data, core_uuid, schema_version, &limits)?;
+    Ok(JsonString(json))
+}
+
+#[pg_extern]
+fn bytea_to_json_text_auto(data: Vec<u8>) -> Result<JsonString, DecodeError> {
+    let limits = pg_debyte_pgrx::limits();
+    let json = pg_debyte_pgrx::decode_auto_json(&data, &limits)?;
+    Ok(JsonString(json))
+}
+
+// The jsonb variants reuse the size-limited JSON text writer and Postgres' own jsonb input,
+// so no `serde_json::Value` is built per row.
+extension_sql!(
+    r#"
+CREATE FUNCTION bytea_to_json_by_id(data bytea, type_id uuid, schema_version smallint)
+RETURNS jsonb STRICT LANGUAGE sql
+AS $$ SELECT bytea_to_json_text_by_id(data, type_id, schema_version)::jsonb $$;
+
+CREATE FUNCTION bytea_to_json_auto(data bytea)
+RETURNS jsonb STRICT LANGUAGE sql
+AS $$ SELECT bytea_to_json_text_auto(data)::jsonb $$;
+"#,
+    name = "bytea_to_json_jsonb",
+    requires = [bytea_to_json_text_by_id, bytea_to_json_text_auto],
+);
+
+#[pg_extern]
+fn bytea_to_json_stream<'a>(data: &'a [u8]) -> Result<SetOfIterator<'a, JsonB>, DecodeError> {
+    let limits = pg_debyte_pgrx::limits();
+    let records = pg_debyte_pgrx::decode_stream(data, &limits)?;
+    Ok(SetOfIterator::new(records.map(|record| match record {
+        Ok(value) => JsonB(value),
+        Err(err) => error!("{err}"),
+    })))
+}
+
+#[pg_extern]
+fn pg_debyte_metadata(data: Vec<u8>) -> Result<JsonB, DecodeError> {
+    let limits = pg_debyte_pgrx::limits();
+    let value = pg_debyte_pgrx::decode_metadata(&data, &limits)?;
     Ok(JsonB(value))
 }
 
 #[pg_extern]
-fn bytea_to_json_auto(data: Vec<u8>) -> Result<JsonB, DecodeError> {
+fn pg_debyte_inspect(data: Vec<u8>) -> Result<JsonB, DecodeError> {
     let limits = pg_debyte_pgrx::limits();
-    let value = pg_debyte_pgrx::decode_auto(&data, &limits)?;
+    let value = pg_debyte_pgrx::inspect_envelope(&data, &limits)?;
     Ok(JsonB(value))
 }
 
+#[pg_extern]
+fn pg_debyte_rewrap(data: Vec<u8>, actions: JsonB) -> Result<Vec<u8>, DecodeError> {
+    let limits = pg_debyte_pgrx::limits();
+    pg_debyte_pgrx::rewrap(&data, &actions.0, &limits)
+}
+
+#[pg_extern]
+fn pg_debyte_rotate_key(data: Vec<u8>, new_key_id: i64) -> Result<Vec<u8>, DecodeError> {
+    let limits = pg_debyte_pgrx::limits();
+    pg_debyte_pgrx::rotate_key(&data, new_key_id, &limits)
+}
+
+// Both run encode with the registry's keys: left open they would sign, strip or re-encrypt
+// arbitrary payloads for any role.
+extension_sql!(
+    r#"
+REVOKE EXECUTE ON FUNCTION pg_debyte_rewrap(bytea, jsonb), pg_debyte_rotate_key(bytea, bigint)
+    FROM PUBLIC;
+"#,
+    name = "pg_debyte_revoke_encode",
+    requires = [pg_debyte_rewrap, pg_debyte_rotate_key],
+);
+
+/// Trains a zstd dictionary from `samples`, e.g.
+/// `array(SELECT payload FROM events TABLESAMPLE SYSTEM (1))`, stores it in
+/// `pg_debyte_dictionaries` and returns its new id. NULL samples are skipped.
+#[pg_extern]
+fn pg_debyte_train_dictionary(
+    samples: Vec<Option<Vec<u8>>>,
+    max_size: default!(i32, 16384),
+) -> Result<i32, DecodeError> {
+    let limits = pg_debyte_pgrx::limits();
+    let samples: Vec<Vec<u8>> = samples.into_iter().flatten().collect();
+    let dictionary = pg_debyte_pgrx::train_dictionary(&samples, max_size, &limits)?;
+    Spi::connect_mut(|client| {
+        client
+            .update(
+                "INSERT INTO pg_debyte_dictionaries (dictionary) VALUES ($1) RETURNING id",
+                Some(1),
+                &[dictionary.into()],
+            )?
+            .first()
+            .get_one::<i32>()
+    })
+    .map_err(|err| DecodeError::InvalidDictionary(format!("storing dictionary: {err}")))?
+    .ok_or_else(|| DecodeError::InvalidDictionary("insert returned no id".to_string()))
+}
+
 #[cfg(any(test, feature = "pg_test"))]
 #[pg_schema]
 mod tests {
     use super::*;
     use hex::encode;
     use pg_debyte_core::action::ActionSpec;
-    use pg_debyte_core::codec::BincodeCodec;
-    use pg_debyte_core::encode::encode_to_envelope;
+    use pg_debyte_core::codec::{BincodeCodec, CborCodec, Codec};
+    use pg_debyte_core::encode::{encode_to_envelope, encode_to_envelope_with_metadata};
+    use pg_debyte_core::metadata::EnvelopeMetadata;
     use pg_debyte_core::registry::StaticRegistry;
+    use pg_debyte_core::stream::append_frame;
     use pg_debyte_core::types::{EncodeLimits, TypeKey};
     use serde_json::json;
 
@@ -127,6 +359,44 @@ mod tests {
         encode(envelope)
     }
 
+    fn demo_envelope_with_metadata_hex() -> String {
+        let key = TypeKey {
+            type_id: CoreUuid::from_bytes([0x11; 16]),
+            schema_version: 1,
+        };
+        let codec = BincodeCodec::new(1, 32 * 1024 * 1024);
+        let limits = EncodeLimits::new(32 * 1024 * 1024);
+        let registry = StaticRegistry::new(&[], &[]);
+        let metadata = EnvelopeMetadata::new()
+            .producer("demo-service")
+            .created_at_ms(1_700_000_000_000)
+            .tenant_id("tenant-1");
+
+        let envelope = encode_to_envelope_with_metadata(
+            &(1u32, "demo".to_string()),
+            &codec,
+            key,
+            &[],
+            &metadata,
+            &registry,
+            &limits,
+        )
+        .unwrap();
+        encode(envelope)
+    }
+
+    fn schemaless_envelope_hex<C: Codec>(codec: &C, type_id: CoreUuid) -> String {
+        let key = TypeKey {
+            type_id,
+            schema_version: 1,
+        };
+        let value = json!({"id": 7, "tags": ["a", "b"], "nested": {"ok": true}});
+        let limits = EncodeLimits::new(32 * 1024 * 1024);
+        let registry = StaticRegistry::new(&[], &[]);
+        let envelope = encode_to_envelope(&value, codec, key, &[], &registry, &limits).unwrap();
+        encode(envelope)
+    }
+
     #[pg_test]
     fn test_bytea_to_json_by_id() {
         let json = Spi::get_one::<JsonB>(
@@ -148,6 +418,631 @@ mod tests {
         assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
     }
 
+    #[pg_test]
+    fn test_bytea_to_json_text_by_id() {
+        let json = Spi::get_one::<JsonB>(
+            "SELECT bytea_to_json_text_by_id(decode('010464656d6f', 'hex'), \
+             '11111111-1111-1111-1111-111111111111'::uuid, 1::smallint)::jsonb",
+        )
+        .expect("spi")
+        .expect("json");
+
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_text_auto() {
+        let hex = demo_envelope_hex();
+        let query = format!("SELECT bytea_to_json_text_auto(decode('{}', 'hex'))", hex);
+        let json = Spi::get_one::<JsonString>(&query)
+            .expect("spi")
+            .expect("json");
+
+        assert_eq!(json.0, r#"{"id":1,"label":"demo"}"#);
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_text_guc_max_json_bytes() {
+        let ok = PgTryBuilder::new(|| {
+            Spi::run("SET LOCAL pg_debyte.max_json_bytes = 8").expect("set guc");
+            let _ = Spi::get_one::<JsonString>(
+                "SELECT bytea_to_json_text_by_id(decode('010464656d6f', 'hex'), \
+                 '11111111-1111-1111-1111-111111111111'::uuid, 1::smallint)",
+            )
+            .expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_with_metadata() {
+        let hex = demo_envelope_with_metadata_hex();
+        let query = format!("SELECT bytea_to_json_auto(decode('{}', 'hex'))", hex);
+        let json = Spi::get_one::<JsonB>(&query).expect("spi").expect("json");
+
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_schemaless() {
+        let hex = schemaless_envelope_hex(
+            &RmpCodec::new(2, 32 * 1024 * 1024),
+            CoreUuid::from_bytes([0xc5; 16]),
+        );
+        let query = format!("SELECT bytea_to_json_auto(decode('{}', 'hex'))", hex);
+        let json = Spi::get_one::<JsonB>(&query).expect("spi").expect("json");
+
+        assert_eq!(
+            json.0,
+            json!({"id": 7, "tags": ["a", "b"], "nested": {"ok": true}})
+        );
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_schemaless_fallback() {
+        let hex = schemaless_envelope_hex(
+            &CborCodec::new(3, 32 * 1024 * 1024),
+            CoreUuid::from_bytes([0xd6; 16]),
+        );
+        let query = format!("SELECT bytea_to_json_auto(decode('{}', 'hex'))", hex);
+        Spi::run("SET LOCAL pg_debyte.schemaless_fallback = on").expect("set guc");
+        let json = Spi::get_one::<JsonB>(&query).expect("spi").expect("json");
+
+        assert_eq!(
+            json.0,
+            json!({"id": 7, "tags": ["a", "b"], "nested": {"ok": true}})
+        );
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_schemaless_fallback_disabled() {
+        let hex = schemaless_envelope_hex(
+            &CborCodec::new(3, 32 * 1024 * 1024),
+            CoreUuid::from_bytes([0xd6; 16]),
+        );
+        let ok = PgTryBuilder::new(|| {
+            let query = format!("SELECT bytea_to_json_auto(decode('{}', 'hex'))", hex);
+            let _ = Spi::get_one::<JsonB>(&query).expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    fn migrating_envelope_hex<C: Codec>(codec: &C) -> String {
+        let key = TypeKey {
+            type_id: CoreUuid::from_bytes([0xe7; 16]),
+            schema_version: 1,
+        };
+        let record = DemoRecordSecond {
+            id: 1,
+            text: "second".to_string(),
+            flag: true,
+        };
+        let limits = EncodeLimits::new(32 * 1024 * 1024);
+        let registry = StaticRegistry::new(&[], &[]);
+        let envelope = encode_to_envelope(&record, codec, key, &[], &registry, &limits).unwrap();
+        encode(envelope)
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_registered_codecs() {
+        for hex in [
+            migrating_envelope_hex(&BincodeCodec::new(1, 32 * 1024 * 1024)),
+            migrating_envelope_hex(&RmpCodec::new(2, 32 * 1024 * 1024)),
+        ] {
+            let query = format!("SELECT bytea_to_json_auto(decode('{}', 'hex'))", hex);
+            let json = Spi::get_one::<JsonB>(&query).expect("spi").expect("json");
+
+            assert_eq!(json.0, json!({"id": 1, "text": "second", "flag": true}));
+        }
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_unregistered_codec() {
+        let hex = migrating_envelope_hex(&CborCodec::new(3, 32 * 1024 * 1024));
+        let ok = PgTryBuilder::new(|| {
+            let query = format!("SELECT bytea_to_json_auto(decode('{}', 'hex'))", hex);
+            let _ = Spi::get_one::<JsonB>(&query).expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_stream() {
+        let mut stream = Vec::new();
+        append_frame(&mut stream, &hex::decode(demo_envelope_hex()).unwrap()).unwrap();
+        append_frame(
+            &mut stream,
+            &hex::decode(demo_envelope_with_metadata_hex()).unwrap(),
+        )
+        .unwrap();
+        append_frame(
+            &mut stream,
+            &hex::decode("504744430101010100010464656d6f").unwrap(),
+        )
+        .unwrap();
+        let query = format!(
+            "SELECT jsonb_agg(record) FROM bytea_to_json_stream(decode('{}', 'hex')) AS record",
+            encode(stream)
+        );
+        let json = Spi::get_one::<JsonB>(&query).expect("spi").expect("json");
+
+        assert_eq!(
+            json.0,
+            json!([
+                {"id": 1, "label": "demo"},
+                {"id": 1, "label": "demo"},
+                {"id": 1, "label": "demo"},
+            ])
+        );
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_stream_truncated() {
+        let ok = PgTryBuilder::new(|| {
+            let _ = Spi::get_one::<JsonB>(
+                "SELECT jsonb_agg(record) FROM \
+                 bytea_to_json_stream(decode('0a000000010464', 'hex')) AS record",
+            )
+            .expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_pg_debyte_metadata() {
+        let hex = demo_envelope_with_metadata_hex();
+        let query = format!("SELECT pg_debyte_metadata(decode('{}', 'hex'))", hex);
+        let json = Spi::get_one::<JsonB>(&query).expect("spi").expect("json");
+
+        assert_eq!(
+            json.0,
+            json!({
+                "producer": "demo-service",
+                "created_at_ms": 1_700_000_000_000i64,
+                "tenant_id": "tenant-1",
+            })
+        );
+    }
+
+    #[pg_test]
+    fn test_pg_debyte_inspect() {
+        let hex = demo_envelope_hex();
+        let query = format!("SELECT pg_debyte_inspect(decode('{}', 'hex'))", hex);
+        let json = Spi::get_one::<JsonB>(&query).expect("spi").expect("json");
+
+        assert_eq!(json.0["magic"], json!("PGDEBYTE"));
+        assert_eq!(json.0["version"], json!(2));
+        assert_eq!(
+            json.0["type_id"],
+            json!("11111111-1111-1111-1111-111111111111")
+        );
+        assert_eq!(json.0["schema_version"], json!(1));
+        assert_eq!(json.0["codec_id"], json!(1));
+        assert_eq!(json.0["actions"], json!([]));
+        assert_eq!(json.0["checksum"]["valid"], json!(true));
+        assert_eq!(json.0["payload_len"], json!(6));
+        assert_eq!(json.0["known_type"], json!(true));
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_compact() {
+        let json = Spi::get_one::<JsonB>(
+            "SELECT bytea_to_json_auto(decode('504744430101010100010464656d6f', 'hex'))",
+        )
+        .expect("spi")
+        .expect("json");
+
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+    }
+
+    #[pg_test]
+    fn test_bytea_to_json_auto_confluent() {
+        let json = Spi::get_one::<JsonB>(
+            "SELECT bytea_to_json_auto(decode('000000002a010464656d6f', 'hex'))",
+        )
+        .expect("spi")
+        .expect("json");
+
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+    }
+
+    #[pg_test]
+    fn test_auto_rejects_unknown_confluent_schema_id() {
+        let ok = PgTryBuilder::new(|| {
+            let _ = Spi::get_one::<JsonB>(
+                "SELECT bytea_to_json_auto(decode('000000002b010464656d6f', 'hex'))",
+            )
+            .expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_pg_debyte_rewrap() {
+        let hex = demo_envelope_hex();
+        let rewrapped = format!(
+            "pg_debyte_rewrap(decode('{}', 'hex'), '[{{\"id\": 1, \"params\": \"13\"}}]'::jsonb)",
+            hex
+        );
+
+        let inspect = Spi::get_one::<JsonB>(&format!("SELECT pg_debyte_inspect({rewrapped})"))
+            .expect("spi")
+            .expect("json");
+        assert_eq!(
+            inspect.0["actions"],
+            json!([{"id": 1, "flags": 0, "params": "13"}])
+        );
+        assert_eq!(
+            inspect.0["type_id"],
+            json!("11111111-1111-1111-1111-111111111111")
+        );
+
+        let json = Spi::get_one::<JsonB>(&format!("SELECT bytea_to_json_auto({rewrapped})"))
+            .expect("spi")
+            .expect("json");
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+
+        let unwrapped = Spi::get_one::<JsonB>(&format!(
+            "SELECT pg_debyte_inspect(pg_debyte_rewrap({rewrapped}, '[]'::jsonb))"
+        ))
+        .expect("spi")
+        .expect("json");
+        assert_eq!(unwrapped.0["actions"], json!([]));
+        assert_eq!(unwrapped.0["payload_len"], json!(6));
+    }
+
+    const DEMO_KEY_HEX: &str = "4242424242424242424242424242424242424242424242424242424242424242";
+
+    fn encrypted_demo_sql() -> String {
+        format!(
+            "pg_debyte_rewrap(decode('{}', 'hex'), '[{{\"id\": 2, \"params\": \"07000000\"}}]'::jsonb)",
+            demo_envelope_hex()
+        )
+    }
+
+    #[pg_test]
+    fn test_aead_with_guc_keys() {
+        Spi::run(&format!("SET LOCAL pg_debyte.keys = '7:{DEMO_KEY_HEX}'")).expect("set keys");
+        let encrypted = encrypted_demo_sql();
+
+        let inspect = Spi::get_one::<JsonB>(&format!("SELECT pg_debyte_inspect({encrypted})"))
+            .expect("spi")
+            .expect("json");
+        let params = inspect.0["actions"][0]["params"].as_str().expect("params");
+        assert_eq!(params.len(), 32);
+        assert!(params.starts_with("07000000"));
+
+        let json = Spi::get_one::<JsonB>(&format!("SELECT bytea_to_json_auto({encrypted})"))
+            .expect("spi")
+            .expect("json");
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+    }
+
+    #[pg_test]
+    fn test_aead_rejects_wrong_key() {
+        Spi::run(&format!("SET LOCAL pg_debyte.keys = '7:{DEMO_KEY_HEX}'")).expect("set keys");
+        let encrypted = Spi::get_one::<Vec<u8>>(&format!("SELECT {}", encrypted_demo_sql()))
+            .expect("spi")
+            .expect("bytea");
+
+        let ok = PgTryBuilder::new(|| {
+            Spi::run(&format!(
+                "SET LOCAL pg_debyte.keys = '7:{}'",
+                "17".repeat(32)
+            ))
+            .expect("set keys");
+            let _ = Spi::get_one::<JsonB>(&format!(
+                "SELECT bytea_to_json_auto(decode('{}', 'hex'))",
+                encode(&encrypted)
+            ))
+            .expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_pg_debyte_rotate_key() {
+        Spi::run(&format!(
+            "SET LOCAL pg_debyte.keys = '7:{DEMO_KEY_HEX}, 8:{}'",
+            "17".repeat(32)
+        ))
+        .expect("set keys");
+        let encrypted = Spi::get_one::<Vec<u8>>(&format!(
+            "SELECT pg_debyte_rewrap(decode('{}', 'hex'), \
+             '[{{\"id\": 3, \"params\": \"07000000\"}}]'::jsonb)",
+            demo_envelope_hex()
+        ))
+        .expect("spi")
+        .expect("bytea");
+        let rotated = Spi::get_one::<Vec<u8>>(&format!(
+            "SELECT pg_debyte_rotate_key(decode('{}', 'hex'), 8)",
+            encode(&encrypted)
+        ))
+        .expect("spi")
+        .expect("bytea");
+        assert_eq!(
+            &rotated[rotated.len() - 16..],
+            &encrypted[encrypted.len() - 16..]
+        );
+
+        let inspect = Spi::get_one::<JsonB>(&format!(
+            "SELECT pg_debyte_inspect(decode('{}', 'hex'))",
+            encode(&rotated)
+        ))
+        .expect("spi")
+        .expect("json");
+        let params = inspect.0["actions"][0]["params"].as_str().expect("params");
+        assert!(params.starts_with("08000000"));
+
+        Spi::run(&format!(
+            "SET LOCAL pg_debyte.keys = '8:{}'",
+            "17".repeat(32)
+        ))
+        .expect("set keys");
+        let json = Spi::get_one::<JsonB>(&format!(
+            "SELECT bytea_to_json_auto(decode('{}', 'hex'))",
+            encode(&rotated)
+        ))
+        .expect("spi")
+        .expect("json");
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+    }
+
+    #[pg_test]
+    fn test_hmac_signature_with_guc_keys() {
+        Spi::run(&format!("SET LOCAL pg_debyte.keys = '9:{DEMO_KEY_HEX}'")).expect("set keys");
+        let signed = Spi::get_one::<Vec<u8>>(&format!(
+            "SELECT pg_debyte_rewrap(decode('{}', 'hex'), \
+             '[{{\"id\": 4, \"params\": \"09000000\"}}]'::jsonb)",
+            demo_envelope_hex()
+        ))
+        .expect("spi")
+        .expect("bytea");
+
+        let json = Spi::get_one::<JsonB>(&format!(
+            "SELECT bytea_to_json_auto(decode('{}', 'hex'))",
+            encode(&signed)
+        ))
+        .expect("spi")
+        .expect("json");
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+
+        let ok = PgTryBuilder::new(|| {
+            Spi::run(&format!(
+                "SET LOCAL pg_debyte.keys = '9:{}'",
+                "17".repeat(32)
+            ))
+            .expect("set keys");
+            let _ = Spi::get_one::<JsonB>(&format!(
+                "SELECT bytea_to_json_auto(decode('{}', 'hex'))",
+                encode(&signed)
+            ))
+            .expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_required_signature_rejects_unsigned() {
+        static KEYS: pg_debyte_core::StaticKeyProvider =
+            pg_debyte_core::StaticKeyProvider::new(&[(SIGNING_KEY_ID, &[0x42; 32])]);
+        static SIGNING: StaticRegistry =
+            StaticRegistry::new(&[], &[&SIGNATURE_ACTION]).with_key_provider(&KEYS);
+
+        let record = DemoRecord {
+            id: 1,
+            label: "demo".to_string(),
+        };
+        let key = TypeKey {
+            type_id: DEMO_SIGNED_TYPE_ID,
+            schema_version: 1,
+        };
+        let limits = EncodeLimits::new(32 * 1024 * 1024);
+        let signed = encode_to_envelope(
+            &record,
+            &DEMO_CODEC,
+            key,
+            &[ActionSpec::new(
+                SIGNATURE_ACTION_ID,
+                0,
+                SIGNING_KEY_ID.to_le_bytes().to_vec(),
+            )],
+            &SIGNING,
+            &limits,
+        )
+        .unwrap();
+        let unsigned =
+            encode_to_envelope(&record, &DEMO_CODEC, key, &[], &SIGNING, &limits).unwrap();
+
+        Spi::run(&format!("SET LOCAL pg_debyte.keys = '9:{DEMO_KEY_HEX}'")).expect("set keys");
+        let json = Spi::get_one::<JsonB>(&format!(
+            "SELECT bytea_to_json_auto(decode('{}', 'hex'))",
+            encode(&signed)
+        ))
+        .expect("spi")
+        .expect("json");
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+
+        let ok = PgTryBuilder::new(|| {
+            let _ = Spi::get_one::<JsonB>(&format!(
+                "SELECT bytea_to_json_auto(decode('{}', 'hex'))",
+                encode(&unsigned)
+            ))
+            .expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_pg_debyte_train_dictionary() {
+        let dictionary_id = Spi::get_one::<i32>(
+            "SELECT pg_debyte_train_dictionary(array(\
+                 SELECT convert_to(format('{\"id\": %s, \"label\": \"demo-%s\", \
+                 \"status\": \"%s\"}', i, i % 7, CASE WHEN i % 2 = 0 THEN 'open' \
+                 ELSE 'closed' END), 'UTF8') FROM generate_series(1, 1000) AS i), 1024)",
+        )
+        .expect("spi")
+        .expect("dictionary id");
+        let stored = Spi::get_one::<i32>(&format!(
+            "SELECT length(dictionary) FROM pg_debyte_dictionaries WHERE id = {dictionary_id}"
+        ))
+        .expect("spi")
+        .expect("length");
+        assert!(stored > 0 && stored <= 1024);
+
+        let params = encode(ZstdAction::dictionary_params(3, dictionary_id as u32));
+        let compressed = Spi::get_one::<Vec<u8>>(&format!(
+            "SELECT pg_debyte_rewrap(decode('{}', 'hex'), \
+             '[{{\"id\": 1, \"params\": \"{params}\"}}]'::jsonb)",
+            demo_envelope_hex()
+        ))
+        .expect("spi")
+        .expect("bytea");
+        let json = Spi::get_one::<JsonB>(&format!(
+            "SELECT bytea_to_json_auto(decode('{}', 'hex'))",
+            encode(&compressed)
+        ))
+        .expect("spi")
+        .expect("json");
+        assert_eq!(json.0, json!({"id": 1, "label": "demo"}));
+    }
+
+    #[pg_test]
+    fn test_dictionaries_are_immutable() {
+        Spi::run("INSERT INTO pg_debyte_dictionaries (dictionary) VALUES ('\\x00'::bytea)")
+            .expect("insert");
+        let dictionary_id = Spi::get_one::<i32>("SELECT max(id) FROM pg_debyte_dictionaries")
+            .expect("spi")
+            .expect("id");
+        let public_select = Spi::get_one::<bool>(
+            "SELECT has_table_privilege('public', 'pg_debyte_dictionaries', 'SELECT')",
+        )
+        .expect("spi")
+        .expect("bool");
+        assert!(public_select);
+
+        let triggers = Spi::get_one::<i64>(
+            "SELECT count(*) FROM pg_trigger \
+             WHERE tgrelid = 'pg_debyte_dictionaries'::regclass AND NOT tgisinternal",
+        )
+        .expect("spi")
+        .expect("count");
+        assert_eq!(triggers, 2);
+
+        // The failed statement aborts the transaction, so only one can be tried here.
+        let ok = PgTryBuilder::new(|| {
+            Spi::run(&format!(
+                "UPDATE pg_debyte_dictionaries SET dictionary = '\\x01' WHERE id = {dictionary_id}"
+            ))
+            .expect("spi");
+            true
+        })
+        .catch_others(|_| false)
+        .execute();
+        assert!(!ok);
+    }
+
+    #[pg_test]
+    fn test_dictionaries_are_dumped() {
+        let dumped = Spi::get_one::<i64>(
+            "SELECT count(*) FROM pg_extension, unnest(extconfig) AS config \
+             WHERE config IN ('pg_debyte_dictionaries'::regclass, \
+                 pg_get_serial_sequence('pg_debyte_dictionaries', 'id')::regclass)",
+        )
+        .expect("spi")
+        .expect("count");
+        assert_eq!(dumped, 2);
+    }
+
+    #[pg_test]
+    fn test_dictionary_lookup_keeps_postgres_error() {
+        Spi::run("ALTER TABLE pg_debyte_dictionaries RENAME TO pg_debyte_dictionaries_moved")
+            .expect("rename");
+        let code = PgTryBuilder::new(|| {
+            let _ = Spi::get_one::<Vec<u8>>(&format!(
+                "SELECT pg_debyte_rewrap(decode('{}', 'hex'), \
+                 '[{{\"id\": 1, \"params\": \"03e7030000\"}}]'::jsonb)",
+                demo_envelope_hex()
+            ));
+            None
+        })
+        .catch_others(|err| match err {
+            pgrx::pg_sys::panic::CaughtError::PostgresError(report) => {
+                Some(report.sql_error_code())
+            }
+            _ => None,
+        })
+        .execute();
+
+        assert_eq!(code, Some(PgSqlErrorCode::ERRCODE_UNDEFINED_TABLE));
+    }
+
+    #[pg_test]
+    fn test_rewrap_is_not_public() {
+        Spi::run("CREATE ROLE debyte_untrusted").expect("create role");
+        Spi::run("SET LOCAL ROLE debyte_untrusted").expect("set role");
+        let code = PgTryBuilder::new(|| {
+            let _ = Spi::get_one::<Vec<u8>>(&format!(
+                "SELECT pg_debyte_rewrap(decode('{}', 'hex'), \
+                 '[{{\"id\": 4, \"params\": \"01000000\"}}]'::jsonb)",
+                demo_envelope_hex()
+            ));
+            None
+        })
+        .catch_others(|err| match err {
+            pgrx::pg_sys::panic::CaughtError::PostgresError(report) => {
+                Some(report.sql_error_code())
+            }
+            _ => None,
+        })
+        .execute();
+
+        assert_eq!(code, Some(PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE));
+    }
+
+    #[pg_test]
+    fn test_rotate_key_is_not_public() {
+        let public_execute = Spi::get_one::<bool>(
+            "SELECT has_function_privilege('public', \
+             'pg_debyte_rotate_key(bytea, bigint)', 'EXECUTE')",
+        )
+        .expect("spi")
+        .expect("bool");
+        assert!(!public_execute);
+    }
+
     #[pg_test]
     fn test_auto_rejects_raw() {
         let ok = PgTryBuilder::new(|| {
@@ -207,6 +1102,18 @@ mod tests {
         assert_eq!(json.0, json!({"id": 1, "text": "second", "flag": true}));
     }
 
+    #[pg_test]
+    fn test_bytea_to_json_know_schema_msgpack() {
+        let json = Spi::get_one::<JsonB>(
+            "SELECT bytea_to_json_demo_record_msgpack(decode(\
+             '83a2696401a474657874a67365636f6e64a4666c6167c3', 'hex'))",
+        )
+        .expect("spi")
+        .expect("json");
+
+        assert_eq!(json.0, json!({"id": 1, "text": "second", "flag": true}));
+    }
+
     #[pg_test]
     fn test_know_schema_guc_max_input_bytes() {
         let ok = PgTryBuilder::new(|| {
@@ -1,13 +1,30 @@
-use pg_debyte_core::action::{ActionSpec, ActionSpecRef};
-use pg_debyte_core::envelope::{try_parse, ParsedEnvelope};
+use pg_debyte_core::action::{
+    decode_actions_in, ensure_required_actions, ActionContext, ActionSpec,
+};
+use pg_debyte_core::compact::try_parse_compact;
+use pg_debyte_core::dictionary::{train_zstd_dictionary, Dictionary, DictionaryProvider};
+use pg_debyte_core::dyn_codec::DynCodec;
+use pg_debyte_core::encode::{rewrap_envelope, rotate_envelope_key};
+use pg_debyte_core::envelope::{
+    try_parse, try_parse_confluent, try_parse_unverified, ConfluentView, ParsedEnvelope, MAGIC,
+};
 use pg_debyte_core::error::DecodeError;
+use pg_debyte_core::json::ensure_json_limit;
+use pg_debyte_core::keys::{parse_keys, KeyBytes, KeyProvider};
 use pg_debyte_core::registry::Registry;
-use pg_debyte_core::types::{DecodeLimits, TypeKey};
+use pg_debyte_core::stream::frames;
+use pg_debyte_core::types::{DecodeLimits, EncodeLimits, TypeKey};
 use pg_debyte_core::DecoderEntry;
 use pgrx::guc::{GucContext, GucFlags, GucRegistry, GucSetting};
+use pgrx::pg_sys::panic::CaughtError;
+use pgrx::spi::{Spi, SpiError};
+use serde::Deserialize;
 use std::any::Any;
-use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::OnceLock;
+use std::borrow::Cow;
+use std::ffi::CString;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
 use uuid::Uuid;
 
 static REGISTRY: OnceLock<&'static dyn Registry> = OnceLock::new();
@@ -17,6 +34,9 @@ const DEFAULT_MAX_BYTES: i32 = 950 * 1024 * 1024;
 static MAX_INPUT_BYTES: GucSetting<i32> = GucSetting::<i32>::new(DEFAULT_MAX_BYTES);
 static MAX_OUTPUT_BYTES: GucSetting<i32> = GucSetting::<i32>::new(DEFAULT_MAX_BYTES);
 static MAX_JSON_BYTES: GucSetting<i32> = GucSetting::<i32>::new(DEFAULT_MAX_BYTES);
+static SCHEMALESS_FALLBACK: GucSetting<bool> = GucSetting::<bool>::new(false);
+static KEYS: GucSetting<Option<CString>> = GucSetting::<Option<CString>>::new(None);
+static KEY_FILE: GucSetting<Option<CString>> = GucSetting::<Option<CString>>::new(None);
 
 pub fn init_gucs() {
     GucRegistry::define_int_guc(
@@ -49,6 +69,163 @@ pub fn init_gucs() {
         GucContext::Userset,
         GucFlags::default(),
     );
+    GucRegistry::define_bool_guc(
+        c"pg_debyte.schemaless_fallback",
+        c"Decode unknown types with the registry fallback decoders",
+        c"Lets bytea_to_json_auto decode self-describing payloads of unregistered types",
+        &SCHEMALESS_FALLBACK,
+        GucContext::Userset,
+        GucFlags::default(),
+    );
+    GucRegistry::define_string_guc(
+        c"pg_debyte.keys",
+        c"Keys for keyed pg_debyte actions, as id:hex pairs",
+        c"Read by GUC_KEY_PROVIDER; only superusers can see or set it",
+        &KEYS,
+        GucContext::Suset,
+        GucFlags::SUPERUSER_ONLY | GucFlags::NO_SHOW_ALL | GucFlags::NOT_IN_SAMPLE,
+    );
+    GucRegistry::define_string_guc(
+        c"pg_debyte.key_file",
+        c"File with keys for keyed pg_debyte actions, one id:hex pair per line",
+        c"Read by GUC_KEY_PROVIDER after pg_debyte.keys",
+        &KEY_FILE,
+        GucContext::Sighup,
+        GucFlags::SUPERUSER_ONLY,
+    );
+}
+
+/// Key provider backed by the `pg_debyte.keys` and `pg_debyte.key_file` GUCs,
+/// for [`StaticRegistry::with_key_provider`](pg_debyte_core::StaticRegistry::with_key_provider).
+pub static GUC_KEY_PROVIDER: GucKeyProvider = GucKeyProvider {
+    guc_cache: Mutex::new(None),
+    file_cache: Mutex::new(None),
+};
+
+pub struct GucKeyProvider {
+    guc_cache: Mutex<Option<KeyGucCache>>,
+    file_cache: Mutex<Option<KeyFileCache>>,
+}
+
+/// Parsed `pg_debyte.keys`, reparsed only when the setting's value changes. The raw value
+/// is key material too, so it is zeroized like the parsed keys.
+struct KeyGucCache {
+    value: KeyBytes,
+    keys: Vec<(u32, KeyBytes)>,
+}
+
+/// Parsed key file, reloaded when its path or modification time changes.
+struct KeyFileCache {
+    path: CString,
+    modified: Option<SystemTime>,
+    keys: Vec<(u32, KeyBytes)>,
+}
+
+impl KeyProvider for GucKeyProvider {
+    fn key(&self, key_id: u32) -> Result<Option<KeyBytes>, DecodeError> {
+        if let Some(value) = KEYS.get() {
+            let value = KeyBytes::new(value.into_bytes());
+            let mut cache = self
+                .guc_cache
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if cache.as_ref().is_none_or(|cached| cached.value != value) {
+                let keys = parse_keys(&String::from_utf8_lossy(&value))?;
+                *cache = Some(KeyGucCache { value, keys });
+            }
+            if let Some(key) = cache
+                .as_ref()
+                .and_then(|cached| find_key(&cached.keys, key_id))
+            {
+                return Ok(Some(key));
+            }
+        }
+        let Some(path) = KEY_FILE.get() else {
+            return Ok(None);
+        };
+        let file_path = std::path::Path::new(path.to_str().map_err(|_| {
+            DecodeError::InvalidKey("pg_debyte.key_file is not valid UTF-8".to_string())
+        })?);
+        let modified = std::fs::metadata(file_path)?.modified().ok();
+        let mut cache = self
+            .file_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let stale = match cache.as_ref() {
+            Some(cached) => {
+                cached.path != path || modified.is_none() || cached.modified != modified
+            }
+            None => true,
+        };
+        if stale {
+            let keys = parse_keys(&std::fs::read_to_string(file_path)?)?;
+            *cache = Some(KeyFileCache {
+                path,
+                modified,
+                keys,
+            });
+        }
+        Ok(cache
+            .as_ref()
+            .and_then(|cached| find_key(&cached.keys, key_id)))
+    }
+}
+
+fn find_key(keys: &[(u32, KeyBytes)], key_id: u32) -> Option<KeyBytes> {
+    keys.iter()
+        .find(|(id, _)| *id == key_id)
+        .map(|(_, key)| key.clone())
+}
+
+/// Dictionary provider running `query` over SPI with the dictionary id as its only
+/// (`integer`) parameter, e.g. `SELECT dictionary FROM pg_debyte_dictionaries WHERE id = $1`.
+/// Loaded dictionaries are cached for the life of the backend, so stored rows must not change.
+pub struct SpiDictionaryProvider {
+    query: &'static str,
+    cache: Mutex<Vec<(u32, Dictionary)>>,
+}
+
+impl SpiDictionaryProvider {
+    pub const fn new(query: &'static str) -> Self {
+        Self {
+            query,
+            cache: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn cached(&self, id: u32) -> Option<Dictionary> {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|(cached_id, _)| *cached_id == id)
+            .map(|(_, dictionary)| dictionary.clone())
+    }
+}
+
+impl DictionaryProvider for SpiDictionaryProvider {
+    fn dictionary(&self, id: u32) -> Result<Option<Dictionary>, DecodeError> {
+        if let Some(dictionary) = self.cached(id) {
+            return Ok(Some(dictionary));
+        }
+        let Ok(sql_id) = i32::try_from(id) else {
+            return Ok(None);
+        };
+        let dictionary = match Spi::get_one_with_args::<Vec<u8>>(self.query, &[sql_id.into()]) {
+            Ok(Some(dictionary)) => Dictionary::from(dictionary),
+            Ok(None) | Err(SpiError::InvalidPosition) => return Ok(None),
+            Err(err) => {
+                return Err(DecodeError::InvalidDictionary(format!(
+                    "loading dictionary {id}: {err}"
+                )))
+            }
+        };
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push((id, dictionary.clone()));
+        Ok(Some(dictionary))
+    }
 }
 
 pub fn set_registry(registry: &'static dyn Registry) {
@@ -78,18 +255,22 @@ pub fn decode_by_id(
 ) -> Result<serde_json::Value, DecodeError> {
     catch_unwind_decode(|| {
         ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
-        let reg = registry()?;
-        let key = TypeKey {
-            type_id,
-            schema_version: schema_version as u16,
-        };
-        let entry = reg
-            .lookup_decoder(key)
-            .ok_or(DecodeError::UnknownType(key))?;
-        let payload = apply_actions_refs(reg, entry.default_actions(), data, limits)?;
-        let value = entry.decode_payload(&payload, limits)?;
-        ensure_json_limit(&value, limits)?;
-        Ok(value)
+        let resolved = resolve_by_id(registry()?, data, type_id, schema_version, limits)?;
+        decode_value(&resolved, limits)
+    })
+}
+
+/// Like [`decode_by_id`], but returns JSON text without building a `serde_json::Value`.
+pub fn decode_by_id_json(
+    data: &[u8],
+    type_id: Uuid,
+    schema_version: i16,
+    limits: &DecodeLimits,
+) -> Result<String, DecodeError> {
+    catch_unwind_result(|| {
+        ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+        let resolved = resolve_by_id(registry()?, data, type_id, schema_version, limits)?;
+        decode_text(&resolved, limits)
     })
 }
 
@@ -100,70 +281,388 @@ pub fn decode_know_schema(
 ) -> Result<serde_json::Value, DecodeError> {
     catch_unwind_decode(|| {
         ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
-        let value = if decoder.default_actions().is_empty() {
-            decoder.decode_payload(data, limits)?
-        } else {
-            let reg = registry()?;
-            let payload = apply_actions_refs(reg, decoder.default_actions(), data, limits)?;
-            decoder.decode_payload(&payload, limits)?
-        };
-        ensure_json_limit(&value, limits)?;
-        Ok(value)
+        let resolved = resolve_know_schema(data, decoder, limits)?;
+        decode_value(&resolved, limits)
+    })
+}
+
+/// Like [`decode_know_schema`], but returns JSON text without building a `serde_json::Value`.
+pub fn decode_know_schema_json(
+    data: &[u8],
+    decoder: &dyn DecoderEntry,
+    limits: &DecodeLimits,
+) -> Result<String, DecodeError> {
+    catch_unwind_result(|| {
+        ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+        let resolved = resolve_know_schema(data, decoder, limits)?;
+        decode_text(&resolved, limits)
     })
 }
 
 pub fn decode_auto(data: &[u8], limits: &DecodeLimits) -> Result<serde_json::Value, DecodeError> {
+    catch_unwind_decode(|| {
+        ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+        decode_auto_record(registry()?, data, limits)
+    })
+}
+
+/// Like [`decode_auto`], but returns JSON text without building a `serde_json::Value`.
+pub fn decode_auto_json(data: &[u8], limits: &DecodeLimits) -> Result<String, DecodeError> {
+    catch_unwind_result(|| {
+        ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+        let resolved = resolve_auto(registry()?, data, limits)?;
+        decode_text(&resolved, limits)
+    })
+}
+
+/// Decodes the length-prefixed envelopes in `data` one record per `next`, so only the
+/// current record's JSON is held at a time. Frames borrow from `data`, which is bounded
+/// by `max_input_bytes`.
+pub fn decode_stream<'a>(
+    data: &'a [u8],
+    limits: &DecodeLimits,
+) -> Result<impl Iterator<Item = Result<serde_json::Value, DecodeError>> + 'a, DecodeError> {
+    ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+    let reg = registry()?;
+    let limits = *limits;
+    Ok(frames(data)
+        .map(move |frame| catch_unwind_result(|| decode_auto_record(reg, frame?, &limits))))
+}
+
+fn decode_auto_record(
+    reg: &dyn Registry,
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<serde_json::Value, DecodeError> {
+    let resolved = resolve_auto(reg, data, limits)?;
+    decode_value(&resolved, limits)
+}
+
+/// Decoder, optional foreign codec and action-decoded payload for one record.
+struct Resolved<'e, 'a> {
+    entry: &'e dyn DecoderEntry,
+    codec: Option<&'static dyn DynCodec>,
+    payload: Cow<'a, [u8]>,
+}
+
+fn resolve_by_id<'a>(
+    reg: &dyn Registry,
+    data: &'a [u8],
+    type_id: Uuid,
+    schema_version: i16,
+    limits: &DecodeLimits,
+) -> Result<Resolved<'static, 'a>, DecodeError> {
+    let key = TypeKey {
+        type_id,
+        schema_version: schema_version as u16,
+    };
+    let entry = reg
+        .lookup_decoder(key)
+        .ok_or(DecodeError::UnknownType(key))?;
+    let payload = apply_default_actions(reg, entry, data, limits)?;
+    Ok(Resolved {
+        entry,
+        codec: None,
+        payload,
+    })
+}
+
+fn resolve_know_schema<'e, 'a>(
+    data: &'a [u8],
+    decoder: &'e dyn DecoderEntry,
+    limits: &DecodeLimits,
+) -> Result<Resolved<'e, 'a>, DecodeError> {
+    let payload = if decoder.default_actions().is_empty() && decoder.required_actions().is_empty() {
+        Cow::Borrowed(data)
+    } else {
+        apply_default_actions(registry()?, decoder, data, limits)?
+    };
+    Ok(Resolved {
+        entry: decoder,
+        codec: None,
+        payload,
+    })
+}
+
+fn resolve_auto<'a>(
+    reg: &dyn Registry,
+    data: &'a [u8],
+    limits: &DecodeLimits,
+) -> Result<Resolved<'static, 'a>, DecodeError> {
+    let parsed = try_parse(data)?;
+    let (key, codec_id, actions, envelope_payload) = match parsed {
+        ParsedEnvelope::Envelope(view) => (view.key, view.codec_id, view.actions, view.payload),
+        ParsedEnvelope::None => match try_parse_compact(data)? {
+            Some(view) => (
+                view.resolve_key(reg)?,
+                view.codec_id,
+                view.actions,
+                view.payload,
+            ),
+            None => match try_parse_confluent(data) {
+                Some(framed) => return resolve_confluent(reg, framed, limits),
+                None => return Err(DecodeError::BadEnvelope("no envelope")),
+            },
+        },
+    };
+
+    let entry = match reg.lookup_decoder(key) {
+        Some(entry) => entry,
+        None if SCHEMALESS_FALLBACK.get() => reg
+            .lookup_fallback_decoder(codec_id)
+            .ok_or(DecodeError::UnknownType(key))?,
+        None => return Err(DecodeError::UnknownType(key)),
+    };
+    let codec = if codec_id == entry.codec_id() {
+        None
+    } else if entry.accepts_codec(codec_id) {
+        Some(
+            reg.lookup_codec(codec_id)
+                .ok_or(DecodeError::UnknownCodec(codec_id))?,
+        )
+    } else {
+        return Err(DecodeError::UnknownCodec(codec_id));
+    };
+
+    ensure_required_actions(
+        entry.required_actions(),
+        actions.iter().map(|action| action.id),
+    )?;
+    let context = ActionContext::new(reg)
+        .with_key(key)
+        .with_codec_id(codec_id);
+    let payload = decode_actions_in(&context, actions, envelope_payload, limits)?;
+    Ok(Resolved {
+        entry,
+        codec,
+        payload,
+    })
+}
+
+fn resolve_confluent<'a>(
+    reg: &dyn Registry,
+    framed: ConfluentView<'a>,
+    limits: &DecodeLimits,
+) -> Result<Resolved<'static, 'a>, DecodeError> {
+    let key = reg
+        .lookup_schema_id(framed.schema_id)
+        .ok_or(DecodeError::UnknownSchemaId(framed.schema_id))?;
+    let entry = reg
+        .lookup_decoder(key)
+        .ok_or(DecodeError::UnknownType(key))?;
+    let payload = entry.strip_confluent_framing(framed.payload)?;
+    let payload = apply_default_actions(reg, entry, payload, limits)?;
+    Ok(Resolved {
+        entry,
+        codec: None,
+        payload,
+    })
+}
+
+fn decode_value(
+    resolved: &Resolved<'_, '_>,
+    limits: &DecodeLimits,
+) -> Result<serde_json::Value, DecodeError> {
+    let value = match resolved.codec {
+        Some(codec) => resolved
+            .entry
+            .decode_payload_with(codec, &resolved.payload, limits)?,
+        None => resolved.entry.decode_payload(&resolved.payload, limits)?,
+    };
+    ensure_json_limit(&value, limits)?;
+    Ok(value)
+}
+
+fn decode_text(resolved: &Resolved<'_, '_>, limits: &DecodeLimits) -> Result<String, DecodeError> {
+    let json = match resolved.codec {
+        Some(codec) => resolved
+            .entry
+            .decode_payload_json_with(codec, &resolved.payload, limits)?,
+        None => resolved
+            .entry
+            .decode_payload_json(&resolved.payload, limits)?,
+    };
+    String::from_utf8(json).map_err(|err| DecodeError::Json(err.to_string()))
+}
+
+pub fn decode_metadata(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<serde_json::Value, DecodeError> {
+    catch_unwind_decode(|| {
+        ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+        match try_parse(data)? {
+            ParsedEnvelope::None => Err(DecodeError::BadEnvelope("no envelope")),
+            ParsedEnvelope::Envelope(view) => Ok(view.metadata.to_json()),
+        }
+    })
+}
+
+pub fn inspect_envelope(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<serde_json::Value, DecodeError> {
     catch_unwind_decode(|| {
         ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
         let reg = registry()?;
-        let parsed = try_parse(data)?;
-        let envelope = match parsed {
+        let envelope = match try_parse_unverified(data)? {
             ParsedEnvelope::None => return Err(DecodeError::BadEnvelope("no envelope")),
             ParsedEnvelope::Envelope(view) => view,
         };
 
-        let entry = reg
-            .lookup_decoder(envelope.key)
-            .ok_or(DecodeError::UnknownType(envelope.key))?;
-        if envelope.codec_id != entry.codec_id() {
-            return Err(DecodeError::UnknownCodec(envelope.codec_id));
-        }
+        let actions: Vec<serde_json::Value> = envelope
+            .actions
+            .iter()
+            .map(|action| {
+                serde_json::json!({
+                    "id": action.id,
+                    "flags": action.flags,
+                    "params": hex::encode(action.params),
+                })
+            })
+            .collect();
+        let checksum = envelope.checksum.map(|stored| {
+            let valid = !matches!(try_parse(data), Err(DecodeError::ChecksumMismatch { .. }));
+            serde_json::json!({
+                "value": format!("{stored:#010x}"),
+                "valid": valid,
+            })
+        });
 
-        let payload = apply_actions(reg, &envelope.actions, envelope.payload, limits)?;
-        let value = entry.decode_payload(&payload, limits)?;
-        ensure_json_limit(&value, limits)?;
-        Ok(value)
+        Ok(serde_json::json!({
+            "magic": String::from_utf8_lossy(MAGIC),
+            "version": envelope.version,
+            "type_id": envelope.key.type_id.to_string(),
+            "schema_version": envelope.key.schema_version,
+            "codec_id": envelope.codec_id,
+            "actions": actions,
+            "metadata": envelope.metadata.to_json(),
+            "checksum": checksum,
+            "payload_len": envelope.payload.len(),
+            "known_type": reg.lookup_decoder(envelope.key).is_some(),
+        }))
     })
 }
 
-fn apply_actions(
-    reg: &dyn Registry,
-    actions: &[ActionSpec],
-    payload: &[u8],
+#[derive(Deserialize)]
+struct ActionJson {
+    id: u16,
+    #[serde(default)]
+    flags: u8,
+    #[serde(default)]
+    params: String,
+}
+
+/// Parses `[{"id": 1, "flags": 0, "params": "<hex>"}, ...]` into action specs.
+pub fn actions_from_json(actions: &serde_json::Value) -> Result<Vec<ActionSpec>, DecodeError> {
+    let actions: Vec<ActionJson> = serde_json::from_value(actions.clone())?;
+    actions
+        .into_iter()
+        .map(|action| {
+            let params = hex::decode(&action.params)
+                .map_err(|err| DecodeError::Json(format!("invalid action params: {err}")))?;
+            Ok(ActionSpec::new(action.id, action.flags, params))
+        })
+        .collect()
+}
+
+pub fn rewrap(
+    data: &[u8],
+    actions: &serde_json::Value,
     limits: &DecodeLimits,
 ) -> Result<Vec<u8>, DecodeError> {
-    let mut buffer = payload.to_vec();
-    for action in actions.iter().rev() {
-        let handler = reg
-            .lookup_action(action.id)
-            .ok_or(DecodeError::UnknownAction(action.id))?;
-        buffer = handler.decode(&buffer, limits, &action.params)?;
+    catch_unwind_result(|| {
+        ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+        let reg = registry()?;
+        let actions = actions_from_json(actions)?;
+        let encode_limits = EncodeLimits::new(limits.max_output_bytes);
+        rewrap_envelope(data, &actions, reg, limits, &encode_limits)
+    })
+}
+
+/// Re-wraps the envelope's data keys under `new_key_id` without re-encrypting the payload.
+pub fn rotate_key(
+    data: &[u8],
+    new_key_id: i64,
+    limits: &DecodeLimits,
+) -> Result<Vec<u8>, DecodeError> {
+    catch_unwind_result(|| {
+        ensure_limit("input_bytes", data.len(), limits.max_input_bytes)?;
+        let new_key_id = u32::try_from(new_key_id)
+            .map_err(|_| DecodeError::InvalidKey(format!("key id out of range: {new_key_id}")))?;
+        rotate_envelope_key(data, new_key_id, registry()?)
+    })
+}
+
+/// Trains a zstd dictionary of at most `max_size` bytes. Envelopes in `samples` are reduced
+/// to their codec payload first, so rows that are already compressed still train usefully.
+pub fn train_dictionary(
+    samples: &[Vec<u8>],
+    max_size: i32,
+    limits: &DecodeLimits,
+) -> Result<Vec<u8>, DecodeError> {
+    catch_unwind_result(|| {
+        let max_size = usize::try_from(max_size)
+            .ok()
+            .filter(|size| *size > 0)
+            .ok_or_else(|| {
+                DecodeError::InvalidDictionary(format!("max_size must be positive: {max_size}"))
+            })?;
+        ensure_limit("dictionary_bytes", max_size, limits.max_output_bytes)?;
+        let reg = registry()?;
+        let payloads = samples
+            .iter()
+            .map(|sample| {
+                ensure_limit("input_bytes", sample.len(), limits.max_input_bytes)?;
+                training_payload(reg, sample, limits)
+            })
+            .collect::<Result<Vec<_>, DecodeError>>()?;
+        train_zstd_dictionary(&payloads, max_size)
+    })
+}
+
+/// The codec payload of an envelope, or `data` itself when it is not one.
+fn training_payload<'a>(
+    reg: &dyn Registry,
+    data: &'a [u8],
+    limits: &DecodeLimits,
+) -> Result<Cow<'a, [u8]>, DecodeError> {
+    if let ParsedEnvelope::Envelope(view) = try_parse(data)? {
+        let context = ActionContext::new(reg)
+            .with_key(view.key)
+            .with_codec_id(view.codec_id);
+        return decode_actions_in(&context, view.actions, view.payload, limits);
     }
-    Ok(buffer)
+    if let Some(view) = try_parse_compact(data)? {
+        let context = ActionContext::new(reg)
+            .with_key(view.resolve_key(reg)?)
+            .with_codec_id(view.codec_id);
+        return decode_actions_in(&context, view.actions, view.payload, limits);
+    }
+    Ok(Cow::Borrowed(data))
 }
 
-fn apply_actions_refs(
+/// Undoes the entry's default actions on a bare payload, which must include its required ones.
+fn apply_default_actions<'a>(
     reg: &dyn Registry,
-    actions: &[ActionSpecRef],
-    payload: &[u8],
+    entry: &dyn DecoderEntry,
+    payload: &'a [u8],
     limits: &DecodeLimits,
-) -> Result<Vec<u8>, DecodeError> {
-    let mut buffer = payload.to_vec();
+) -> Result<Cow<'a, [u8]>, DecodeError> {
+    let actions = entry.default_actions();
+    ensure_required_actions(
+        entry.required_actions(),
+        actions.iter().map(|action| action.id),
+    )?;
+    let context = ActionContext::new(reg)
+        .with_key(entry.key())
+        .with_codec_id(entry.codec_id());
+    let mut buffer = Cow::Borrowed(payload);
     for action in actions.iter().rev() {
         let handler = reg
             .lookup_action(action.id)
             .ok_or(DecodeError::UnknownAction(action.id))?;
-        buffer = handler.decode(&buffer, limits, action.params)?;
+        buffer = Cow::Owned(handler.decode_in(&context, &buffer, limits, action.params)?);
     }
     Ok(buffer)
 }
@@ -179,17 +678,23 @@ fn ensure_limit(context: &'static str, actual: usize, limit: usize) -> Result<()
     Ok(())
 }
 
-fn ensure_json_limit(value: &serde_json::Value, limits: &DecodeLimits) -> Result<(), DecodeError> {
-    let json = serde_json::to_vec(value)?;
-    ensure_limit("json_bytes", json.len(), limits.max_json_bytes)
-}
-
 fn catch_unwind_decode<F>(func: F) -> Result<serde_json::Value, DecodeError>
 where
     F: FnOnce() -> Result<serde_json::Value, DecodeError>,
+{
+    catch_unwind_result(func)
+}
+
+/// Maps Rust panics to `DecodeError::Panic`. Postgres errors raised inside `func`, e.g. by the
+/// SPI query of `SpiDictionaryProvider`, keep unwinding so pgrx rethrows them with their
+/// original SQLSTATE and the transaction is cleaned up as usual.
+fn catch_unwind_result<F, T>(func: F) -> Result<T, DecodeError>
+where
+    F: FnOnce() -> Result<T, DecodeError>,
 {
     match catch_unwind(AssertUnwindSafe(func)) {
         Ok(result) => result,
+        Err(panic_err) if panic_err.is::<CaughtError>() => resume_unwind(panic_err),
         Err(panic_err) => Err(DecodeError::Panic(panic_message(panic_err))),
     }
 }